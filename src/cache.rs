@@ -0,0 +1,113 @@
+pub mod cache {
+    use csv::StringRecord;
+    use std::collections::HashMap;
+
+    /// Number of consecutive rows read from the file at once when a row is not cached.
+    pub const DEFAULT_BLOCK_ROWS: usize = 256;
+    /// Number of blocks kept in memory before the least recently used block is dropped.
+    pub const DEFAULT_CACHE_BLOCKS: usize = 64;
+
+    /// Blocks of consecutive records around the rows being viewed, so that scrolling only reads
+    /// from the file the rows not seen recently. Once `capacity` blocks are cached, loading another
+    /// drops the block used least recently.
+    ///
+    /// Rows are numbered by their position in the view, so the cache must be cleared whenever the
+    /// rows shown change, e.g. when the view is sorted or filtered.
+    #[derive(Debug, Clone)]
+    pub struct RecordCache {
+        block_rows: usize,
+        capacity: usize,
+        // block number to the tick it was last used and its records
+        blocks: HashMap<usize, (u64, Vec<StringRecord>)>,
+        tick: u64,
+    }
+
+    impl Default for RecordCache {
+        fn default() -> Self {
+            RecordCache::new(DEFAULT_BLOCK_ROWS, DEFAULT_CACHE_BLOCKS)
+        }
+    }
+
+    impl RecordCache {
+        /// Creates an empty cache of up to `capacity` blocks of `block_rows` records each.
+        pub fn new(block_rows: usize, capacity: usize) -> RecordCache {
+            RecordCache {
+                block_rows: block_rows.max(1),
+                capacity: capacity.max(1),
+                blocks: HashMap::new(),
+                tick: 0,
+            }
+        }
+
+        /// Returns the record at row `pos`. When its block is not cached, `load` is called with
+        /// the first row of the block and the number of rows in a block to read it.
+        /// Returns `None` if the loaded block is too short to hold the row.
+        ///
+        /// # Example
+        /// ```
+        /// use csv::StringRecord;
+        /// use csv_viewer::cache::cache::RecordCache;
+        ///
+        /// let mut loads = Vec::new();
+        /// let mut load = |start: usize, n: usize| {
+        ///     loads.push(start);
+        ///     (start..(start + n).min(10))
+        ///         .map(|row| StringRecord::from(vec![row.to_string()]))
+        ///         .collect()
+        /// };
+        /// let mut cache = RecordCache::new(4, 2);
+        /// assert_eq!(cache.get(5, &mut load).unwrap().get(0), Some("5"));
+        /// assert_eq!(cache.get(6, &mut load).unwrap().get(0), Some("6"));
+        /// assert_eq!(cache.get(9, &mut load).unwrap().get(0), Some("9"));
+        /// assert_eq!(cache.get(10, &mut load), None);
+        /// // the block of rows 4 to 7 was used least recently, so it was dropped for rows 0 to 3
+        /// assert!(cache.get(0, &mut load).is_some());
+        /// assert!(cache.get(7, &mut load).is_some());
+        /// assert_eq!(loads, vec![4, 8, 0, 4]);
+        /// ```
+        pub fn get<F>(&mut self, pos: usize, load: F) -> Option<&StringRecord>
+        where
+            F: FnOnce(usize, usize) -> Vec<StringRecord>,
+        {
+            let block = pos / self.block_rows;
+            self.tick += 1;
+            if !self.blocks.contains_key(&block) {
+                if self.blocks.len() >= self.capacity {
+                    self.evict();
+                }
+                let records = load(block * self.block_rows, self.block_rows);
+                self.blocks.insert(block, (self.tick, records));
+            }
+            let (used, records) = self.blocks.get_mut(&block)?;
+            *used = self.tick;
+            records.get(pos % self.block_rows)
+        }
+
+        /// Drops every cached block.
+        pub fn clear(&mut self) {
+            self.blocks.clear();
+        }
+
+        /// Returns the number of blocks cached.
+        pub fn len(&self) -> usize {
+            self.blocks.len()
+        }
+
+        /// Returns true if no blocks are cached.
+        pub fn is_empty(&self) -> bool {
+            self.blocks.is_empty()
+        }
+
+        /// Drops the block used least recently.
+        fn evict(&mut self) {
+            let oldest = self
+                .blocks
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(block, _)| *block);
+            if let Some(block) = oldest {
+                self.blocks.remove(&block);
+            }
+        }
+    }
+}
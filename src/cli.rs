@@ -0,0 +1,141 @@
+pub mod cli {
+    use std::error::Error;
+    use std::fs::File;
+    use std::io::{self, BufWriter, Read, Write};
+    use std::process;
+
+    /// Text shown by `--help`.
+    pub const USAGE: &str = "\
+Usage: csv_viewer [OPTIONS] [FILE]
+
+Opens FILE in the viewer. When no file is given and data is piped in, opens the piped data.
+
+Options:
+  -d, --delimiter <DELIMITER>  Delimiter of the file: , ; | : or tab (sniffed by default)
+      --header                 The first row of the file is a header row
+      --no-header              The first row of the file is data, not a header row
+  -g, --goto <N>               Scroll to row N when the file opens
+  -h, --help                   Print this help";
+
+    /// Options given on the command line when starting the viewer.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct Args {
+        pub file_path: Option<String>,
+        /// `None` sniffs the delimiter from the file.
+        pub delimiter: Option<char>,
+        /// `None` sniffs whether the file has a header row.
+        pub has_headers: Option<bool>,
+        /// Row number to scroll to when the file opens, starting at 1.
+        pub goto: Option<u64>,
+        pub help: bool,
+    }
+
+    /// Parses the command line `args`, not including the program name.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::cli::cli::parse_args;
+    ///
+    /// let args = parse_args(["data.csv", "--delimiter", "tab", "--no-header", "-g", "12"]
+    ///     .map(String::from))
+    /// .unwrap();
+    /// assert_eq!(args.file_path, Some(String::from("data.csv")));
+    /// assert_eq!(args.delimiter, Some('\t'));
+    /// assert_eq!(args.has_headers, Some(false));
+    /// assert_eq!(args.goto, Some(12));
+    /// assert_eq!(parse_args(["--delimiter=;"].map(String::from)).unwrap().delimiter, Some(';'));
+    /// assert!(parse_args(["--goto", "0"].map(String::from)).is_err());
+    /// assert!(parse_args(["a.csv", "b.csv"].map(String::from)).is_err());
+    /// ```
+    pub fn parse_args<I>(args: I) -> Result<Args, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // options taking a value accept it as the next argument or after an `=`
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || -> Result<String, Box<dyn Error>> {
+                match inline_value.clone().or_else(|| args.next()) {
+                    Some(value) => Ok(value),
+                    None => Err(From::from(format!("{} needs a value", name))),
+                }
+            };
+            match name {
+                "-d" | "--delimiter" => {
+                    let text = value()?;
+                    match parse_delimiter(&text) {
+                        Some(delimiter) => parsed.delimiter = Some(delimiter),
+                        None => return Err(From::from(format!("Unknown delimiter {:?}", text))),
+                    }
+                }
+                "--header" => parsed.has_headers = Some(true),
+                "--no-header" => parsed.has_headers = Some(false),
+                "-g" | "--goto" => {
+                    let text = value()?;
+                    match text.parse() {
+                        Ok(row) if row > 0 => parsed.goto = Some(row),
+                        _ => return Err(From::from(format!("Invalid row number {:?}", text))),
+                    }
+                }
+                "-h" | "--help" => parsed.help = true,
+                _ if name.starts_with('-') && name != "-" => {
+                    return Err(From::from(format!("Unknown option {}", name)));
+                }
+                _ if parsed.file_path.is_none() => parsed.file_path = Some(arg.clone()),
+                _ => return Err(From::from(format!("Unexpected argument {}", arg))),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Returns the delimiter named by `text`, either the character itself or its name.
+    /// Only the delimiters the viewer can be set to are accepted.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::cli::cli::parse_delimiter;
+    ///
+    /// assert_eq!(parse_delimiter("|"), Some('|'));
+    /// assert_eq!(parse_delimiter("\\t"), Some('\t'));
+    /// assert_eq!(parse_delimiter("Semicolon"), Some(';'));
+    /// assert_eq!(parse_delimiter("#"), None);
+    /// ```
+    pub fn parse_delimiter(text: &str) -> Option<char> {
+        match text.to_lowercase().as_str() {
+            "," | "comma" => Some(','),
+            "\t" | "\\t" | "tab" => Some('\t'),
+            ";" | "semicolon" => Some(';'),
+            "|" | "pipe" => Some('|'),
+            ":" | "colon" => Some(':'),
+            _ => None,
+        }
+    }
+
+    /// Copies everything read from `input` to a new file in the temporary directory, and returns
+    /// its path. Data piped in can only be read once, so it is spooled to a file the viewer can
+    /// seek in while paging, sorting and finding.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::cli::cli::spool_to_temp_file;
+    ///
+    /// let path = spool_to_temp_file("name,rank\nSelma,1\n".as_bytes()).unwrap();
+    /// assert_eq!(std::fs::read_to_string(&path).unwrap(), "name,rank\nSelma,1\n");
+    /// std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn spool_to_temp_file<R: Read>(mut input: R) -> Result<String, Box<dyn Error>> {
+        let path = std::env::temp_dir()
+            .join(format!("csv_viewer_stdin_{}.csv", process::id()))
+            .display()
+            .to_string();
+        let mut output = BufWriter::new(File::create(&path)?);
+        io::copy(&mut input, &mut output)?;
+        output.flush()?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,125 @@
+pub mod clipboard {
+    use csv::StringRecord;
+    use std::error::Error;
+    use std::mem;
+
+    use crate::export::export::{ExportFormat, ExportOptions, Exporter};
+    use crate::reader::reader::Dialect;
+
+    /// A text format cells are copied to the clipboard in.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum CopyFormat {
+        /// Tab separated, as pasted into spreadsheets.
+        Tsv,
+        Csv,
+        /// A GitHub-flavored Markdown table, with the column headers as its header row.
+        Markdown,
+    }
+
+    impl CopyFormat {
+        /// Returns the name of the format shown to the user.
+        pub fn name(&self) -> &'static str {
+            match self {
+                CopyFormat::Tsv => "TSV",
+                CopyFormat::Csv => "CSV",
+                CopyFormat::Markdown => "Markdown",
+            }
+        }
+    }
+
+    /// Returns `records` as text in `format`. Each record holds the copied cells of a row, under
+    /// `headers`. Only Markdown includes the headers, as the header row of its table.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::clipboard::clipboard::{copy_records, CopyFormat};
+    ///
+    /// let headers = StringRecord::from(vec!["City", "Notes"]);
+    /// let records = vec![
+    ///     StringRecord::from(vec!["Selma", "on the river"]),
+    ///     StringRecord::from(vec!["Kenai", "tab\there"]),
+    /// ];
+    /// let copied = copy_records(&headers, &records, CopyFormat::Tsv).unwrap();
+    /// assert_eq!(copied, "Selma\ton the river\nKenai\t\"tab\there\"\n");
+    /// let copied = copy_records(&headers, &records[..1], CopyFormat::Markdown).unwrap();
+    /// assert_eq!(copied, "| City | Notes |\n| --- | --- |\n| Selma | on the river |\n");
+    /// ```
+    pub fn copy_records(
+        headers: &StringRecord,
+        records: &[StringRecord],
+        format: CopyFormat,
+    ) -> Result<String, Box<dyn Error>> {
+        let options = match format {
+            CopyFormat::Tsv => ExportOptions {
+                dialect: Dialect::with_delimiter('\t'),
+                write_headers: false,
+                ..ExportOptions::new(ExportFormat::Csv)
+            },
+            CopyFormat::Csv => ExportOptions {
+                write_headers: false,
+                ..ExportOptions::new(ExportFormat::Csv)
+            },
+            CopyFormat::Markdown => ExportOptions::new(ExportFormat::Markdown),
+        };
+        let mut exporter = Exporter::new(Vec::new(), headers, &options)?;
+        for record in records {
+            exporter.write_record(record)?;
+        }
+        Ok(String::from_utf8(exporter.finish()?)?)
+    }
+
+    /// Splits text pasted from the clipboard into rows of cells, read as tab separated, as copied
+    /// from a spreadsheet. A cell starting with a quote runs to the closing quote, so it may hold
+    /// tabs and line breaks, with a quote inside it written twice. Text without a tab is one cell
+    /// per line. A blank line is a row of one empty cell, and a line ending at the end of the text
+    /// does not start another row.
+    /// Returns an error if a quoted cell is not closed.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::clipboard::clipboard::parse_pasted;
+    ///
+    /// let rows = parse_pasted("a\t\"b\nc\"\r\nd\te\n").unwrap();
+    /// assert_eq!(rows, vec![vec!["a", "b\nc"], vec!["d", "e"]]);
+    /// assert_eq!(parse_pasted("1, 2\n3").unwrap(), vec![vec!["1, 2"], vec!["3"]]);
+    /// assert_eq!(parse_pasted("1\t2\n\n3").unwrap(), vec![vec!["1", "2"], vec![""], vec!["3"]]);
+    /// assert!(parse_pasted("a\t\"b\n").is_err());
+    /// ```
+    pub fn parse_pasted(text: &str) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut cell = String::new();
+        let mut at_cell_start = true;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if at_cell_start => loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            cell.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => cell.push(c),
+                        None => return Err(From::from("A quoted cell is not closed")),
+                    }
+                },
+                '\t' => row.push(mem::take(&mut cell)),
+                // the line feed of a CRLF line ending ends the row
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' | '\r' => {
+                    row.push(mem::take(&mut cell));
+                    rows.push(mem::take(&mut row));
+                }
+                c => cell.push(c),
+            }
+            at_cell_start = matches!(c, '\t' | '\n' | '\r');
+        }
+        if !row.is_empty() || !cell.is_empty() {
+            row.push(cell);
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
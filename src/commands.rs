@@ -0,0 +1,656 @@
+pub mod commands {
+    use csv::{ByteRecord, StringRecord};
+    use std::error::Error;
+    use std::fs::{self, File};
+    use std::io::{self, BufWriter, Write};
+    use std::path::Path;
+    use std::process;
+
+    use crate::cli::cli::{parse_delimiter, spool_to_temp_file};
+    use crate::compare::compare::{parse_number, CompareType};
+    use crate::export::export::{
+        get_table_name, ExportFormat, ExportOptions, Exporter, EXPORT_FORMATS,
+    };
+    use crate::find::find::{record_matches, FindQuery};
+    use crate::reader::reader::{get_headers_from_file, Dialect};
+    use crate::sniff::sniff::sniff_file;
+    use crate::sort::sort::{sort_records, SortKey};
+
+    /// Exit code of a command that succeeded.
+    pub const EXIT_SUCCESS: i32 = 0;
+    /// Exit code of `find` when no row matches, as `grep` does.
+    pub const EXIT_NO_MATCH: i32 = 1;
+    /// Exit code when the command line is not valid.
+    pub const EXIT_USAGE: i32 = 2;
+    /// Exit code when the command fails, e.g. the file cannot be read or has a malformed record.
+    pub const EXIT_FAILURE: i32 = 3;
+
+    /// Number of rows printed by `head` unless told otherwise.
+    const DEFAULT_HEAD_ROWS: usize = 10;
+
+    /// Text shown by `--help` after the options of the viewer.
+    pub const COMMANDS_USAGE: &str = "\
+Commands, run without a window:
+  csv_viewer count FILE                Print the number of data rows
+  csv_viewer headers FILE              Print the number and name of each column
+  csv_viewer head FILE [-n N]          Print the first N rows (10 by default)
+  csv_viewer slice FILE -s N [-e M]    Print rows N to M, or N to the end of the file
+  csv_viewer find PATTERN FILE         Print the rows matching PATTERN, exiting with 1 if none do
+  csv_viewer sort FILE -k KEY...       Print the rows sorted by each KEY in turn
+  csv_viewer stats FILE                Print the number of values and numeric summary of each column
+  csv_viewer convert FILE -f FORMAT    Print the whole file in FORMAT
+
+FILE may be - to read from stdin. Rows are numbered from 1, not counting the header row.
+
+Command options:
+  -d, --delimiter <DELIMITER>  Delimiter of the file: , ; | : or tab (sniffed by default)
+      --header, --no-header    Whether the first row is a header row (sniffed by default)
+  -f, --format <FORMAT>        Output format: csv, json, ndjson, markdown, html or sql (csv)
+      --output-delimiter <D>   Delimiter of CSV output (,)
+  -o, --output <PATH>          Write to PATH instead of stdout
+  -n, --rows <N>               head: number of rows
+  -s, --start <N>              slice: first row
+  -e, --end <M>                slice: last row
+  -i, --ignore-case            find: ignore case
+  -r, --regex                  find: PATTERN is a regular expression
+  -w, --whole-word             find: match whole words only
+      --whole-cell             find: match whole cells only
+  -c, --column <COLUMN>        find: search only COLUMN, may be repeated
+  -k, --key <COLUMN[:desc][:TYPE]>
+                               sort: column to sort by, TYPE is lexical, case-insensitive,
+                               numeric, natural or date (lexical)
+
+Columns are given by number, from 1, or by name.
+Exit codes: 0 success, 1 no match found, 2 invalid command line, 3 failure.";
+
+    /// The commands run without a window.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum CommandName {
+        Count,
+        Headers,
+        Head,
+        Slice,
+        Find,
+        Sort,
+        Stats,
+        Convert,
+    }
+
+    impl CommandName {
+        pub const ALL: [CommandName; 8] = [
+            CommandName::Count,
+            CommandName::Headers,
+            CommandName::Head,
+            CommandName::Slice,
+            CommandName::Find,
+            CommandName::Sort,
+            CommandName::Stats,
+            CommandName::Convert,
+        ];
+
+        /// Returns the name the command is run by.
+        pub fn name(&self) -> &'static str {
+            match self {
+                CommandName::Count => "count",
+                CommandName::Headers => "headers",
+                CommandName::Head => "head",
+                CommandName::Slice => "slice",
+                CommandName::Find => "find",
+                CommandName::Sort => "sort",
+                CommandName::Stats => "stats",
+                CommandName::Convert => "convert",
+            }
+        }
+
+        /// Returns the command run by `name`, if there is one.
+        pub fn from_name(name: &str) -> Option<CommandName> {
+            CommandName::ALL
+                .iter()
+                .copied()
+                .find(|command| command.name() == name)
+        }
+    }
+
+    /// A command parsed from the command line, with its options.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Command {
+        pub name: CommandName,
+        pub file_path: String,
+        /// `None` sniffs the delimiter from the file.
+        pub delimiter: Option<char>,
+        /// `None` sniffs whether the file has a header row.
+        pub has_headers: Option<bool>,
+        pub format: ExportFormat,
+        pub output_delimiter: char,
+        /// `None` writes to stdout.
+        pub output_path: Option<String>,
+        /// Number of rows printed by `head`.
+        pub rows: usize,
+        /// First and last rows printed by `slice`, numbered from 1.
+        pub start: usize,
+        pub end: Option<usize>,
+        /// What `find` searches for. Its columns are resolved from `find_columns` when run.
+        pub query: FindQuery,
+        pub find_columns: Vec<String>,
+        /// Keys of `sort` as given, resolved against the headers of the file when run.
+        pub sort_keys: Vec<String>,
+    }
+
+    impl Command {
+        /// Returns `name` run on `file_path` with the default options.
+        pub fn new(name: CommandName, file_path: &str) -> Command {
+            Command {
+                name,
+                file_path: file_path.to_string(),
+                delimiter: None,
+                has_headers: None,
+                format: ExportFormat::Csv,
+                output_delimiter: ',',
+                output_path: None,
+                rows: DEFAULT_HEAD_ROWS,
+                start: 1,
+                end: None,
+                query: FindQuery::default(),
+                find_columns: Vec::new(),
+                sort_keys: Vec::new(),
+            }
+        }
+    }
+
+    /// Parses the arguments of command `name`, those after its name on the command line.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::commands::commands::{parse_command, CommandName};
+    /// use csv_viewer::export::export::ExportFormat;
+    ///
+    /// let args = ["-i", "al", "data.csv", "--column", "State", "--format=json"].map(String::from);
+    /// let command = parse_command(CommandName::Find, args).unwrap();
+    /// assert_eq!(command.file_path, "data.csv");
+    /// assert_eq!(command.query.text, "al");
+    /// assert!(command.query.case_insensitive);
+    /// assert_eq!(command.find_columns, vec!["State"]);
+    /// assert_eq!(command.format, ExportFormat::Json);
+    ///
+    /// assert!(parse_command(CommandName::Count, Vec::new()).is_err());
+    /// assert!(parse_command(CommandName::Sort, [String::from("data.csv")]).is_err());
+    /// assert!(parse_command(CommandName::Head, ["data.csv", "-n", "x"].map(String::from)).is_err());
+    /// ```
+    pub fn parse_command<I>(name: CommandName, args: I) -> Result<Command, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut command = Command::new(name, "");
+        let mut positional: Vec<String> = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // options taking a value accept it as the next argument or after an `=`
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => {
+                    (option, Some(value.to_string()))
+                }
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || -> Result<String, Box<dyn Error>> {
+                match inline_value.clone().or_else(|| args.next()) {
+                    Some(value) => Ok(value),
+                    None => Err(From::from(format!("{} needs a value", option))),
+                }
+            };
+            match option {
+                "-d" | "--delimiter" => command.delimiter = Some(get_delimiter_arg(&value()?)?),
+                "--output-delimiter" => command.output_delimiter = get_delimiter_arg(&value()?)?,
+                "--header" => command.has_headers = Some(true),
+                "--no-header" => command.has_headers = Some(false),
+                "-f" | "--format" => command.format = get_format_arg(&value()?)?,
+                "-o" | "--output" => command.output_path = Some(value()?),
+                "-n" | "--rows" => command.rows = get_number_arg(option, &value()?, 0)?,
+                "-s" | "--start" => command.start = get_number_arg(option, &value()?, 1)?,
+                "-e" | "--end" => command.end = Some(get_number_arg(option, &value()?, 1)?),
+                "-i" | "--ignore-case" => command.query.case_insensitive = true,
+                "-r" | "--regex" => command.query.regex = true,
+                "-w" | "--whole-word" => command.query.whole_word = true,
+                "--whole-cell" => command.query.whole_cell = true,
+                "-c" | "--column" => command.find_columns.push(value()?),
+                "-k" | "--key" => command.sort_keys.push(value()?),
+                _ if option.starts_with('-') && option != "-" => {
+                    return Err(From::from(format!("Unknown option {}", option)));
+                }
+                _ => positional.push(arg.clone()),
+            }
+        }
+
+        // find takes the pattern before the file
+        if name == CommandName::Find {
+            if positional.is_empty() {
+                return Err(From::from("find needs a PATTERN"));
+            }
+            command.query.text = positional.remove(0);
+        }
+        command.file_path = match positional.len() {
+            0 => return Err(From::from(format!("{} needs a FILE", name.name()))),
+            1 => positional.remove(0),
+            _ => return Err(From::from(format!("Unexpected argument {}", positional[1]))),
+        };
+        if name == CommandName::Sort && command.sort_keys.is_empty() {
+            return Err(From::from("sort needs at least one --key"));
+        }
+        if command.end.map_or(false, |end| end < command.start) {
+            return Err(From::from("--end is before --start"));
+        }
+        Ok(command)
+    }
+
+    fn get_delimiter_arg(text: &str) -> Result<char, Box<dyn Error>> {
+        match parse_delimiter(text) {
+            Some(delimiter) => Ok(delimiter),
+            None => Err(From::from(format!("Unknown delimiter {:?}", text))),
+        }
+    }
+
+    fn get_format_arg(text: &str) -> Result<ExportFormat, Box<dyn Error>> {
+        EXPORT_FORMATS
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(text))
+            .ok_or_else(|| From::from(format!("Unknown format {:?}", text)))
+    }
+
+    fn get_number_arg(option: &str, text: &str, min: usize) -> Result<usize, Box<dyn Error>> {
+        match text.parse() {
+            Ok(number) if number >= min => Ok(number),
+            _ => Err(From::from(format!(
+                "{} needs a number from {}, not {:?}",
+                option, min, text
+            ))),
+        }
+    }
+
+    /// Runs `command`, writing its output to its output path or to stdout, and returns the exit
+    /// code to exit with. A FILE of `-` reads the data piped in on stdin.
+    pub fn run(command: &Command) -> Result<i32, Box<dyn Error>> {
+        let mut command = command.clone();
+        let spooled = if command.file_path == "-" {
+            command.file_path = spool_to_temp_file(io::stdin().lock())?;
+            Some(command.file_path.clone())
+        } else {
+            None
+        };
+        let result = run_to_output(&command);
+        if let Some(path) = spooled {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    fn run_to_output(command: &Command) -> Result<i32, Box<dyn Error>> {
+        if !Path::new(&command.file_path).is_file() {
+            return Err(From::from(format!("No such file: {}", command.file_path)));
+        }
+        match &command.output_path {
+            Some(output_path) => {
+                if Path::new(output_path).exists()
+                    && fs::canonicalize(output_path)? == fs::canonicalize(&command.file_path)?
+                {
+                    return Err(From::from("The output would overwrite the input file"));
+                }
+                // written next to the output and renamed over it, so a command that fails
+                // leaves the output as it was
+                let temp_path = format!("{}.saving", output_path);
+                let result = File::create(&temp_path)
+                    .map_err(From::from)
+                    .and_then(|file| {
+                        let mut out = BufWriter::new(file);
+                        let code = run_command(command, &mut out)?;
+                        out.flush()?;
+                        Ok(code)
+                    })
+                    .and_then(|code| {
+                        fs::rename(&temp_path, output_path)?;
+                        Ok(code)
+                    });
+                if result.is_err() {
+                    let _ = fs::remove_file(&temp_path);
+                }
+                result
+            }
+            None => {
+                let mut out = BufWriter::new(io::stdout().lock());
+                let code = run_command(command, &mut out)?;
+                out.flush()?;
+                Ok(code)
+            }
+        }
+    }
+
+    /// Runs `command`, writing its output to `out`, and returns the exit code to exit with.
+    /// Rows are written in the format of the command, under the headers of the file. `count`
+    /// writes a bare number, and `headers` and `stats` write a table with a row per column.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::commands::commands::{run_command, Command, CommandName, EXIT_NO_MATCH};
+    /// use csv_viewer::export::export::ExportFormat;
+    ///
+    /// let run = |command: &Command| -> (i32, String) {
+    ///     let mut out = Vec::new();
+    ///     let code = run_command(command, &mut out).unwrap();
+    ///     (code, String::from_utf8(out).unwrap())
+    /// };
+    /// let file = "tests/test_uspop.csv";
+    ///
+    /// assert_eq!(run(&Command::new(CommandName::Count, file)), (0, String::from("201\n")));
+    ///
+    /// let mut command = Command::new(CommandName::Slice, file);
+    /// command.start = 2;
+    /// command.end = Some(3);
+    /// let (_, out) = run(&command);
+    /// assert_eq!(out, "City,State,Population,Latitude,Longitude\n\
+    ///     Kenai,AK,7610,60.5544444,-151.2583333\n\
+    ///     Oakman,AL,,33.7133333,-87.3886111\n");
+    ///
+    /// let mut command = Command::new(CommandName::Find, file);
+    /// command.query.text = String::from("Selma");
+    /// command.format = ExportFormat::Ndjson;
+    /// let (code, out) = run(&command);
+    /// assert_eq!(code, 0);
+    /// assert!(out.starts_with("{\"City\":\"Selma\",\"State\":\"AL\",\"Population\":\"18980\""));
+    /// command.query.text = String::from("Atlantis");
+    /// assert_eq!(run(&command), (EXIT_NO_MATCH, String::new()));
+    ///
+    /// let mut command = Command::new(CommandName::Sort, file);
+    /// command.sort_keys = vec![String::from("Population:desc:numeric")];
+    /// command.format = ExportFormat::Markdown;
+    /// let (_, out) = run(&command);
+    /// assert_eq!(out.lines().nth(2), Some("| Indianapolis | IN | 773283 | 39.7683333 | -86.1580556 |"));
+    ///
+    /// let (_, out) = run(&Command::new(CommandName::Headers, file));
+    /// assert!(out.starts_with("column,name\n1,City\n2,State\n"));
+    ///
+    /// let (_, out) = run(&Command::new(CommandName::Stats, "tests/test_quoted_newlines.csv"));
+    /// assert_eq!(out.lines().next(), Some("column,name,values,empty,numeric,min,max,mean"));
+    /// ```
+    pub fn run_command<W: Write>(command: &Command, out: &mut W) -> Result<i32, Box<dyn Error>> {
+        let (dialect, has_headers) = get_file_dialect(command)?;
+        let headers = get_headers_from_file(command.file_path.clone(), &dialect, has_headers)?;
+        let mut options = ExportOptions {
+            dialect: Dialect::with_delimiter(command.output_delimiter),
+            table_name: match &command.output_path {
+                Some(output_path) => get_table_name(output_path),
+                None => String::from("data"),
+            },
+            write_headers: has_headers,
+            ..ExportOptions::new(command.format)
+        };
+        match command.name {
+            CommandName::Count => {
+                let mut reader = dialect
+                    .reader_builder()
+                    .has_headers(has_headers)
+                    .flexible(true)
+                    .from_path(&command.file_path)?;
+                let mut record = ByteRecord::new();
+                let mut rows: usize = 0;
+                while reader.read_byte_record(&mut record)? {
+                    rows += 1;
+                }
+                writeln!(out, "{}", rows)?;
+                Ok(EXIT_SUCCESS)
+            }
+            CommandName::Headers => {
+                options.write_headers = true;
+                let records = headers
+                    .iter()
+                    .enumerate()
+                    .map(|(column, name)| {
+                        StringRecord::from(vec![(column + 1).to_string(), name.to_string()])
+                    })
+                    .collect();
+                write_table(&["column", "name"], records, &options, out)?;
+                Ok(EXIT_SUCCESS)
+            }
+            CommandName::Head | CommandName::Slice | CommandName::Convert => {
+                let (start, end) = match command.name {
+                    CommandName::Head => (0, Some(command.rows)),
+                    CommandName::Slice => (command.start - 1, command.end),
+                    _ => (0, None),
+                };
+                let mut exporter = Exporter::new(&mut *out, &headers, &options)?;
+                let mut reader = dialect
+                    .reader_builder()
+                    .has_headers(has_headers)
+                    .flexible(true)
+                    .from_path(&command.file_path)?;
+                for (row, result) in reader.records().enumerate() {
+                    if end.map_or(false, |end| row >= end) {
+                        break;
+                    }
+                    let record = result?;
+                    if row >= start {
+                        exporter.write_record(&record)?;
+                    }
+                }
+                exporter.finish()?;
+                Ok(EXIT_SUCCESS)
+            }
+            CommandName::Find => {
+                let mut query = command.query.clone();
+                query.columns = command
+                    .find_columns
+                    .iter()
+                    .map(|column| get_column(column, &headers))
+                    .collect::<Result<_, _>>()?;
+                let regex = query.compile()?;
+                let mut exporter = Exporter::new(&mut *out, &headers, &options)?;
+                let mut reader = dialect
+                    .reader_builder()
+                    .has_headers(has_headers)
+                    .flexible(true)
+                    .from_path(&command.file_path)?;
+                for result in reader.records() {
+                    let record = result?;
+                    if record_matches(&record, &regex, &query) {
+                        exporter.write_record(&record)?;
+                    }
+                }
+                let found = exporter.rows();
+                exporter.finish()?;
+                Ok(if found == 0 {
+                    EXIT_NO_MATCH
+                } else {
+                    EXIT_SUCCESS
+                })
+            }
+            CommandName::Sort => {
+                let keys = command
+                    .sort_keys
+                    .iter()
+                    .map(|key| get_sort_key(key, &headers))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let sorted_path = std::env::temp_dir()
+                    .join(format!("csv_viewer_sorted_{}.csv", process::id()))
+                    .display()
+                    .to_string();
+                let result = sort_records(
+                    command.file_path.clone(),
+                    sorted_path.clone(),
+                    &keys,
+                    &dialect,
+                    has_headers,
+                    &dialect,
+                )
+                .and_then(|_| {
+                    let mut exporter = Exporter::new(&mut *out, &headers, &options)?;
+                    let mut reader = dialect
+                        .reader_builder()
+                        .has_headers(has_headers)
+                        .flexible(true)
+                        .from_path(&sorted_path)?;
+                    for result in reader.records() {
+                        exporter.write_record(&result?)?;
+                    }
+                    exporter.finish()?;
+                    Ok(())
+                });
+                let _ = fs::remove_file(&sorted_path);
+                result?;
+                Ok(EXIT_SUCCESS)
+            }
+            CommandName::Stats => {
+                options.write_headers = true;
+                let records = get_column_stats(command, &dialect, has_headers, &headers)?;
+                let stats_headers = [
+                    "column", "name", "values", "empty", "numeric", "min", "max", "mean",
+                ];
+                write_table(&stats_headers, records, &options, out)?;
+                Ok(EXIT_SUCCESS)
+            }
+        }
+    }
+
+    /// Returns the dialect of the file of `command`, and whether it has a header row. Options
+    /// not given on the command line are sniffed from the head of the file.
+    fn get_file_dialect(command: &Command) -> Result<(Dialect, bool), Box<dyn Error>> {
+        let sniffed = sniff_file(&command.file_path)?;
+        let mut dialect = sniffed.dialect;
+        if let Some(delimiter) = command.delimiter {
+            dialect.delimiter = delimiter as u8;
+        }
+        Ok((dialect, command.has_headers.unwrap_or(sniffed.has_headers)))
+    }
+
+    /// Writes a table made by a command, e.g. the list of columns, in the output format.
+    fn write_table<W: Write>(
+        headers: &[&str],
+        records: Vec<StringRecord>,
+        options: &ExportOptions,
+        out: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut exporter = Exporter::new(out, &StringRecord::from(headers.to_vec()), options)?;
+        for record in &records {
+            exporter.write_record(record)?;
+        }
+        exporter.finish()?;
+        Ok(())
+    }
+
+    /// Returns the column named by `text`, either its number from 1 or its name in `headers`.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::commands::commands::get_column;
+    ///
+    /// let headers = StringRecord::from(vec!["City", "State", "2020"]);
+    /// assert_eq!(get_column("2", &headers).unwrap(), 1);
+    /// assert_eq!(get_column("State", &headers).unwrap(), 1);
+    /// assert_eq!(get_column("2020", &headers).unwrap(), 2);
+    /// assert!(get_column("4", &headers).is_err());
+    /// ```
+    pub fn get_column(text: &str, headers: &StringRecord) -> Result<usize, Box<dyn Error>> {
+        // a column named like a number is found by name first
+        if let Some(column) = headers.iter().position(|name| name == text) {
+            return Ok(column);
+        }
+        match text.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= headers.len() => Ok(number - 1),
+            _ => Err(From::from(format!("Unknown column `{}`", text))),
+        }
+    }
+
+    /// Returns the sort key described by `text`, a column followed by `:desc` to sort it in
+    /// descending order and `:` and the type to compare it as, e.g. "Population:desc:numeric".
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::commands::commands::get_sort_key;
+    /// use csv_viewer::compare::compare::CompareType;
+    ///
+    /// let headers = StringRecord::from(vec!["City", "Population"]);
+    /// let key = get_sort_key("Population:desc:numeric", &headers).unwrap();
+    /// assert_eq!((key.column, key.ascending, key.compare), (1, false, CompareType::Numeric));
+    /// let key = get_sort_key("1:natural", &headers).unwrap();
+    /// assert_eq!((key.column, key.ascending, key.compare), (0, true, CompareType::Natural));
+    /// assert!(get_sort_key("City:sideways", &headers).is_err());
+    /// ```
+    pub fn get_sort_key(text: &str, headers: &StringRecord) -> Result<SortKey, Box<dyn Error>> {
+        let mut parts = text.split(':');
+        let mut key = SortKey::new(get_column(parts.next().unwrap_or(""), headers)?);
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "asc" => key.ascending = true,
+                "desc" => key.ascending = false,
+                "lexical" => key.compare = CompareType::Lexical,
+                "case-insensitive" => key.compare = CompareType::CaseInsensitive,
+                "numeric" => key.compare = CompareType::Numeric,
+                "natural" => key.compare = CompareType::Natural,
+                "date" => key.compare = CompareType::Date,
+                _ => return Err(From::from(format!("Unknown sort key option `{}`", part))),
+            }
+        }
+        Ok(key)
+    }
+
+    /// A summary of the cells of a column.
+    #[derive(Debug, Clone, Default)]
+    struct ColumnStats {
+        values: usize,
+        empty: usize,
+        numeric: usize,
+        min: Option<f64>,
+        max: Option<f64>,
+        sum: f64,
+    }
+
+    /// Reads the whole file to summarise each column: the number of values and empty cells,
+    /// and the number, least, greatest and mean of the values that are numbers.
+    fn get_column_stats(
+        command: &Command,
+        dialect: &Dialect,
+        has_headers: bool,
+        headers: &StringRecord,
+    ) -> Result<Vec<StringRecord>, Box<dyn Error>> {
+        let mut stats = vec![ColumnStats::default(); headers.len()];
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(has_headers)
+            .flexible(true)
+            .from_path(&command.file_path)?;
+        for result in reader.records() {
+            let record = result?;
+            for (column, stat) in stats.iter_mut().enumerate() {
+                let cell = record.get(column).unwrap_or("");
+                if cell.trim().is_empty() {
+                    stat.empty += 1;
+                    continue;
+                }
+                stat.values += 1;
+                if let Some(number) = parse_number(cell, '.') {
+                    stat.numeric += 1;
+                    stat.min = Some(stat.min.map_or(number, |min| min.min(number)));
+                    stat.max = Some(stat.max.map_or(number, |max| max.max(number)));
+                    stat.sum += number;
+                }
+            }
+        }
+        let optional = |number: Option<f64>| number.map_or(String::new(), |n| n.to_string());
+        Ok(stats
+            .iter()
+            .enumerate()
+            .map(|(column, stat)| {
+                let mean = Some(stat.sum / stat.numeric as f64).filter(|_| stat.numeric > 0);
+                StringRecord::from(vec![
+                    (column + 1).to_string(),
+                    headers.get(column).unwrap_or("").to_string(),
+                    stat.values.to_string(),
+                    stat.empty.to_string(),
+                    stat.numeric.to_string(),
+                    optional(stat.min),
+                    optional(stat.max),
+                    optional(mean),
+                ])
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,424 @@
+pub mod compare {
+    use std::cmp::Ordering;
+
+    /// How two cell values are compared when sorting.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum CompareType {
+        /// Byte-wise string comparison.
+        Lexical,
+        /// String comparison ignoring letter case.
+        CaseInsensitive,
+        /// Compares the values as numbers.
+        Numeric,
+        /// Compares runs of digits as numbers and the text between them as strings,
+        /// so "file2" comes before "file10".
+        Natural,
+        /// Compares dates and times, read as ISO-8601 (e.g. "2023-04-01" or "2023-04-01T09:30:00")
+        /// unless a date format is given in the `CompareOptions`.
+        Date,
+    }
+
+    /// Where cells with no value of the compared type sort, whichever the direction of the sort.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum NullOrder {
+        First,
+        Last,
+    }
+
+    /// Settings for reading cell values as the compared type.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct CompareOptions {
+        /// Separates the whole and fractional parts of a number. When it is ',', '.' is taken as
+        /// a thousands separator, otherwise ',' is.
+        pub decimal_separator: char,
+        /// A `strftime`-style format for dates, e.g. "%d/%m/%Y %H:%M". ISO-8601 is used when `None`.
+        /// Supports %Y, %y, %m, %b, %d, %H, %I, %M, %S, %f, %p and %%.
+        pub date_format: Option<String>,
+        /// Where empty cells, and cells that fail to parse as a number or date, are placed.
+        pub nulls: NullOrder,
+    }
+
+    impl Default for CompareOptions {
+        fn default() -> Self {
+            Self {
+                decimal_separator: '.',
+                date_format: None,
+                nulls: NullOrder::Last,
+            }
+        }
+    }
+
+    /// Years from 1970 to 2069 for two-digit "%y" years, as POSIX `strptime` does.
+    const TWO_DIGIT_YEAR_PIVOT: i64 = 69;
+    const MONTH_ABBREVIATIONS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    /// A date and time as year, month, day, hour, minute, second and nanosecond,
+    /// which sorts chronologically.
+    type DateTime = (i64, u32, u32, u32, u32, u32, u32);
+
+    impl CompareType {
+        /// All comparison types, in the order they are offered to the user.
+        pub const ALL: [CompareType; 5] = [
+            CompareType::Lexical,
+            CompareType::CaseInsensitive,
+            CompareType::Numeric,
+            CompareType::Natural,
+            CompareType::Date,
+        ];
+    }
+
+    /// Compares two cells for a sort in the given direction.
+    /// Null cells, those that are empty or can't be read as the compared type, are placed first or
+    /// last according to `options.nulls` regardless of `ascending`, and keep their order among
+    /// themselves.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use csv_viewer::compare::compare::{compare_cells, CompareOptions, CompareType, NullOrder};
+    ///
+    /// let nulls_last = CompareOptions::default();
+    /// assert_eq!(compare_cells("", "9", CompareType::Numeric, true, &nulls_last), Ordering::Greater);
+    /// assert_eq!(compare_cells("", "9", CompareType::Numeric, false, &nulls_last), Ordering::Greater);
+    /// assert_eq!(compare_cells("n/a", "", CompareType::Numeric, true, &nulls_last), Ordering::Equal);
+    ///
+    /// let nulls_first = CompareOptions { nulls: NullOrder::First, ..Default::default() };
+    /// assert_eq!(compare_cells("", "9", CompareType::Numeric, true, &nulls_first), Ordering::Less);
+    /// assert_eq!(compare_cells("9", "18980", CompareType::Numeric, false, &nulls_first), Ordering::Greater);
+    /// ```
+    pub fn compare_cells(
+        a: &str,
+        b: &str,
+        compare: CompareType,
+        ascending: bool,
+        options: &CompareOptions,
+    ) -> Ordering {
+        let null_ordering = match options.nulls {
+            NullOrder::First => Ordering::Less,
+            NullOrder::Last => Ordering::Greater,
+        };
+        match (is_null(a, compare, options), is_null(b, compare, options)) {
+            (true, true) => Ordering::Equal,
+            (true, false) => null_ordering,
+            (false, true) => null_ordering.reverse(),
+            (false, false) => {
+                let ordering = compare_values(a, b, compare, options);
+                if ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            }
+        }
+    }
+
+    /// Returns true if the cell is empty, or can't be read as the compared type.
+    fn is_null(value: &str, compare: CompareType, options: &CompareOptions) -> bool {
+        if value.trim().is_empty() {
+            return true;
+        }
+        match compare {
+            CompareType::Numeric => parse_number(value, options.decimal_separator).is_none(),
+            CompareType::Date => parse_date(value, &options.date_format).is_none(),
+            _ => false,
+        }
+    }
+
+    /// Compares two cell values using `compare`.
+    /// Values that cannot be read as the compared type, such as an empty cell in a numeric column,
+    /// sort after the ones that can, and are compared as strings among themselves.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use csv_viewer::compare::compare::{compare_values, CompareOptions, CompareType};
+    ///
+    /// let options = CompareOptions::default();
+    /// assert_eq!(compare_values("9", "18980", CompareType::Lexical, &options), Ordering::Greater);
+    /// assert_eq!(compare_values("9", "18980", CompareType::Numeric, &options), Ordering::Less);
+    /// assert_eq!(compare_values("9", "18,980", CompareType::Numeric, &options), Ordering::Less);
+    /// assert_eq!(compare_values("", "9", CompareType::Numeric, &options), Ordering::Greater);
+    /// assert_eq!(compare_values("file2", "file10", CompareType::Natural, &options), Ordering::Less);
+    /// assert_eq!(compare_values("apple", "Banana", CompareType::CaseInsensitive, &options), Ordering::Less);
+    /// assert_eq!(
+    ///     compare_values("2023-04-01", "2023-04-01T09:30", CompareType::Date, &options),
+    ///     Ordering::Less
+    /// );
+    ///
+    /// let european = CompareOptions {
+    ///     decimal_separator: ',',
+    ///     date_format: Some(String::from("%d.%m.%Y")),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(compare_values("1.234,5", "999,9", CompareType::Numeric, &european), Ordering::Greater);
+    /// assert_eq!(compare_values("02.01.2023", "01.02.2023", CompareType::Date, &european), Ordering::Less);
+    ///
+    /// let us = CompareOptions {
+    ///     date_format: Some(String::from("%b %d %y %I:%M %p")),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(compare_values("Apr 1 23 9:30 PM", "Apr 1 23 10:15 AM", CompareType::Date, &us), Ordering::Greater);
+    /// assert_eq!(compare_values("Dec 31 99 1:00 AM", "Jan 1 00 1:00 AM", CompareType::Date, &us), Ordering::Less);
+    /// ```
+    pub fn compare_values(
+        a: &str,
+        b: &str,
+        compare: CompareType,
+        options: &CompareOptions,
+    ) -> Ordering {
+        match compare {
+            CompareType::Lexical => a.cmp(b),
+            CompareType::CaseInsensitive => a
+                .to_lowercase()
+                .cmp(&b.to_lowercase())
+                .then_with(|| a.cmp(b)),
+            CompareType::Numeric => compare_parsed(
+                parse_number(a, options.decimal_separator),
+                parse_number(b, options.decimal_separator),
+                a,
+                b,
+            ),
+            CompareType::Natural => compare_natural(a, b),
+            CompareType::Date => compare_parsed(
+                parse_date(a, &options.date_format),
+                parse_date(b, &options.date_format),
+                a,
+                b,
+            ),
+        }
+    }
+
+    /// Compares two values by their parsed form, placing values that failed to parse last.
+    fn compare_parsed<T: PartialOrd>(x: Option<T>, y: Option<T>, a: &str, b: &str) -> Ordering {
+        match (x, y) {
+            (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.cmp(b),
+        }
+    }
+
+    /// Reads a number written with the given decimal separator, ignoring surrounding whitespace
+    /// and thousands separators.
+    pub fn parse_number(value: &str, decimal_separator: char) -> Option<f64> {
+        let thousands_separator = if decimal_separator == ',' { '.' } else { ',' };
+        let mut number = String::new();
+        for c in value.trim().chars() {
+            if c == decimal_separator {
+                number.push('.');
+            } else if c != thousands_separator && c != '_' {
+                number.push(c);
+            }
+        }
+        let number: f64 = number.parse().ok()?;
+        if number.is_nan() {
+            return None;
+        }
+        Some(number)
+    }
+
+    /// Reads a date with `format`, or as ISO-8601 if there is no format.
+    fn parse_date(value: &str, format: &Option<String>) -> Option<DateTime> {
+        match format {
+            Some(format) if !format.is_empty() => parse_formatted_date(value, format),
+            _ => parse_iso_date(value),
+        }
+    }
+
+    /// Compares two strings a run of digits or non-digits at a time.
+    fn compare_natural(a: &str, b: &str) -> Ordering {
+        let mut a_runs = split_digit_runs(a).into_iter();
+        let mut b_runs = split_digit_runs(b).into_iter();
+        loop {
+            let ordering = match (a_runs.next(), b_runs.next()) {
+                (None, None) => return a.cmp(b),
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) => {
+                    let x_digits = x.starts_with(|c: char| c.is_ascii_digit());
+                    let y_digits = y.starts_with(|c: char| c.is_ascii_digit());
+                    if x_digits && y_digits {
+                        compare_digit_runs(x, y)
+                    } else {
+                        x.cmp(y)
+                    }
+                }
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+
+    /// Splits a string into alternating runs of ASCII digits and other characters.
+    fn split_digit_runs(value: &str) -> Vec<&str> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        let mut in_digits = None;
+        for (i, c) in value.char_indices() {
+            let is_digit = c.is_ascii_digit();
+            if in_digits.is_some() && in_digits != Some(is_digit) {
+                runs.push(&value[start..i]);
+                start = i;
+            }
+            in_digits = Some(is_digit);
+        }
+        if start < value.len() {
+            runs.push(&value[start..]);
+        }
+        runs
+    }
+
+    /// Compares two runs of digits by value, without the risk of overflowing an integer.
+    fn compare_digit_runs(a: &str, b: &str) -> Ordering {
+        let a_trimmed = a.trim_start_matches('0');
+        let b_trimmed = b.trim_start_matches('0');
+        a_trimmed
+            .len()
+            .cmp(&b_trimmed.len())
+            .then_with(|| a_trimmed.cmp(b_trimmed))
+            .then_with(|| a.len().cmp(&b.len()))
+    }
+
+    /// Reads an ISO-8601 date, with an optional time separated by 'T' or a space, into a tuple
+    /// of year, month, day, hour, minute, second and nanosecond that sorts chronologically.
+    fn parse_iso_date(value: &str) -> Option<DateTime> {
+        let value = value.trim();
+        let (date, time) = match value.find(['T', ' ']) {
+            Some(i) => (&value[..i], Some(&value[i + 1..])),
+            None => (value, None),
+        };
+
+        let mut date_parts = date.splitn(3, '-');
+        let year: i64 = date_parts.next()?.parse().ok()?;
+        let month: u32 = date_parts.next()?.parse().ok()?;
+        let day: u32 = date_parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let (mut hour, mut minute, mut second, mut nanos) = (0, 0, 0, 0);
+        if let Some(time) = time {
+            // drop a trailing "Z" or UTC offset
+            let time = time.trim_end_matches('Z');
+            let time = match time.rfind(['+', '-']) {
+                Some(i) => &time[..i],
+                None => time,
+            };
+            let mut time_parts = time.splitn(3, ':');
+            hour = time_parts.next()?.parse().ok()?;
+            minute = time_parts.next()?.parse().ok()?;
+            if let Some(seconds) = time_parts.next() {
+                let (whole, fraction) = match seconds.find('.') {
+                    Some(i) => (&seconds[..i], &seconds[i + 1..]),
+                    None => (seconds, ""),
+                };
+                second = whole.parse().ok()?;
+                if !fraction.is_empty() {
+                    let digits: String = fraction
+                        .chars()
+                        .chain("000000000".chars())
+                        .take(9)
+                        .collect();
+                    nanos = digits.parse().ok()?;
+                }
+            }
+        }
+        Some((year, month, day, hour, minute, second, nanos))
+    }
+
+    /// Reads a date laid out as described by a `strftime`-style `format`.
+    /// Numeric fields may have fewer digits than their full width, e.g. "1/2/2023" for "%d/%m/%Y".
+    fn parse_formatted_date(value: &str, format: &str) -> Option<DateTime> {
+        let value = value.trim();
+        let (mut year, mut month, mut day) = (1970, 1, 1);
+        let (mut hour, mut minute, mut second, mut nanos) = (0, 0, 0, 0);
+        let mut pm: Option<bool> = None;
+
+        let mut rest = value;
+        let mut spec = format.chars();
+        while let Some(c) = spec.next() {
+            if c != '%' {
+                rest = rest.strip_prefix(c)?;
+                continue;
+            }
+            match spec.next()? {
+                'Y' => (year, rest) = take_number(rest, 4)?,
+                'y' => {
+                    let (short_year, remainder): (i64, &str) = take_number(rest, 2)?;
+                    year = if short_year < TWO_DIGIT_YEAR_PIVOT {
+                        2000 + short_year
+                    } else {
+                        1900 + short_year
+                    };
+                    rest = remainder;
+                }
+                'm' => (month, rest) = take_number(rest, 2)?,
+                'b' => {
+                    let name = rest.get(..3)?.to_lowercase();
+                    month = MONTH_ABBREVIATIONS.iter().position(|m| *m == name)? as u32 + 1;
+                    rest = &rest[3..];
+                }
+                'd' => (day, rest) = take_number(rest, 2)?,
+                'H' | 'I' => (hour, rest) = take_number(rest, 2)?,
+                'M' => (minute, rest) = take_number(rest, 2)?,
+                'S' => (second, rest) = take_number(rest, 2)?,
+                'f' => {
+                    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+                    if digits.is_empty() {
+                        return None;
+                    }
+                    rest = &rest[digits.len()..];
+                    let digits: String =
+                        digits.chars().chain("000000000".chars()).take(9).collect();
+                    nanos = digits.parse().ok()?;
+                }
+                'p' => {
+                    let marker = rest.get(..2)?.to_uppercase();
+                    pm = match marker.as_str() {
+                        "AM" => Some(false),
+                        "PM" => Some(true),
+                        _ => return None,
+                    };
+                    rest = &rest[2..];
+                }
+                '%' => rest = rest.strip_prefix('%')?,
+                _ => return None,
+            }
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+
+        // convert a 12-hour clock time
+        if let Some(pm) = pm {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour %= 12;
+            if pm {
+                hour += 12;
+            }
+        }
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+            return None;
+        }
+        Some((year, month, day, hour, minute, second, nanos))
+    }
+
+    /// Reads up to `max_digits` digits from the start of `value`, returning the number and the
+    /// rest of the string.
+    fn take_number<T: std::str::FromStr>(value: &str, max_digits: usize) -> Option<(T, &str)> {
+        let len = value
+            .chars()
+            .take(max_digits)
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if len == 0 {
+            return None;
+        }
+        Some((value[..len].parse().ok()?, &value[len..]))
+    }
+}
@@ -0,0 +1,415 @@
+pub mod edit {
+    use csv::StringRecord;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fs::{remove_file, rename};
+    use std::ops::Range;
+
+    use crate::reader::reader::Dialect;
+
+    /// A row of the edited file: a data row of the file on disk, or a row inserted by an edit.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum RowId {
+        File(usize),
+        New(usize),
+    }
+
+    /// A column of the edited file: a column of the file on disk, or a column inserted by an edit.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ColumnId {
+        File(usize),
+        New(usize),
+    }
+
+    /// A run of consecutive rows of the edited file.
+    #[derive(Debug, Clone, PartialEq)]
+    enum RowSegment {
+        File(Range<usize>),
+        New(usize),
+    }
+
+    impl RowSegment {
+        fn len(&self) -> usize {
+            match self {
+                RowSegment::File(rows) => rows.len(),
+                RowSegment::New(_) => 1,
+            }
+        }
+    }
+
+    /// Changes made to a file in the viewer, kept over the file on disk rather than loading it.
+    ///
+    /// Only the edited cells are stored. Rows are kept as runs of consecutive rows of the file
+    /// with the inserted rows between them, so inserting or deleting a row only splits a run.
+    /// Rows and columns are numbered by their position in the edited file, starting at 0, with
+    /// the header row not counted.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::edit::edit::{EditOverlay, RowId};
+    ///
+    /// let headers = StringRecord::from(vec!["name", "rank"]);
+    /// let mut edits = EditOverlay::new(&headers, 3);
+    /// edits.insert_row(1);
+    /// edits.delete_row(3);
+    /// edits.set_cell(0, 1, String::from("9"));
+    /// edits.insert_column(1, "note");
+    ///
+    /// assert_eq!(edits.len(), 3);
+    /// assert_eq!(edits.row_id(0), Some(RowId::File(0)));
+    /// assert_eq!(edits.row_id(2), Some(RowId::File(1)));
+    /// assert_eq!(edits.row_id(3), None);
+    /// assert_eq!(edits.headers(), StringRecord::from(vec!["name", "note", "rank"]));
+    /// assert!(edits.is_cell_edited(0, 2));
+    /// assert!(!edits.is_cell_edited(0, 0));
+    ///
+    /// let record = StringRecord::from(vec!["zeta", "3"]);
+    /// assert_eq!(
+    ///     edits.apply(RowId::File(0), Some(&record)),
+    ///     StringRecord::from(vec!["zeta", "", "9"])
+    /// );
+    /// let ragged = StringRecord::from(vec!["zeta", "3", "extra"]);
+    /// assert_eq!(
+    ///     edits.apply(RowId::File(0), Some(&ragged)),
+    ///     StringRecord::from(vec!["zeta", "", "9", "extra"])
+    /// );
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct EditOverlay {
+        segments: Vec<RowSegment>,
+        columns: Vec<ColumnId>,
+        headers: Vec<String>,
+        // the number of columns of the file on disk, as given by its header row
+        file_columns: usize,
+        cells: HashMap<(RowId, ColumnId), String>,
+        // the next number given to an inserted row or column
+        next_id: usize,
+        rows_changed: bool,
+        columns_changed: bool,
+        modified: bool,
+    }
+
+    impl EditOverlay {
+        /// Returns an overlay with no changes over a file with `headers` and `total_rows` rows.
+        pub fn new(headers: &StringRecord, total_rows: usize) -> EditOverlay {
+            let segments = if total_rows > 0 {
+                vec![RowSegment::File(0..total_rows)]
+            } else {
+                Vec::new()
+            };
+            EditOverlay {
+                segments,
+                columns: (0..headers.len()).map(ColumnId::File).collect(),
+                headers: headers.iter().map(String::from).collect(),
+                file_columns: headers.len(),
+                ..Default::default()
+            }
+        }
+
+        /// Returns the number of rows in the edited file.
+        pub fn len(&self) -> usize {
+            self.segments.iter().map(|segment| segment.len()).sum()
+        }
+
+        /// Returns true if the edited file has no rows.
+        pub fn is_empty(&self) -> bool {
+            self.segments.is_empty()
+        }
+
+        /// Returns true if anything has been changed since the overlay was created.
+        pub fn is_modified(&self) -> bool {
+            self.modified
+        }
+
+        /// Returns true if rows have been inserted or deleted, so rows of the edited file are no
+        /// longer the rows of the file on disk with the same number.
+        pub fn rows_changed(&self) -> bool {
+            self.rows_changed
+        }
+
+        /// Returns true if columns have been inserted or deleted.
+        pub fn columns_changed(&self) -> bool {
+            self.columns_changed
+        }
+
+        /// Returns the row at `pos` of the edited file.
+        pub fn row_id(&self, pos: usize) -> Option<RowId> {
+            let (i, offset) = self.locate(pos)?;
+            match &self.segments[i] {
+                RowSegment::File(rows) => Some(RowId::File(rows.start + offset)),
+                RowSegment::New(id) => Some(RowId::New(*id)),
+            }
+        }
+
+        /// Returns the column of the file on disk shown as `column`, or `None` if it was inserted.
+        pub fn file_column(&self, column: usize) -> Option<usize> {
+            match self.columns.get(column) {
+                Some(ColumnId::File(column)) => Some(*column),
+                _ => None,
+            }
+        }
+
+        /// Returns the header row of the edited file.
+        pub fn headers(&self) -> StringRecord {
+            StringRecord::from(self.headers.clone())
+        }
+
+        /// Returns row `id` with the edits applied, given the record read from the file on disk
+        /// for a row of the file. Cells missing from the record are empty, and fields of the record
+        /// past the columns of the file, as in a ragged file, are kept at the end.
+        pub fn apply(&self, id: RowId, file_record: Option<&StringRecord>) -> StringRecord {
+            let mut record = StringRecord::new();
+            for column in self.columns.iter() {
+                match self.cells.get(&(id, *column)) {
+                    Some(cell) => record.push_field(cell),
+                    None => match (column, file_record) {
+                        (ColumnId::File(c), Some(file_record)) => {
+                            record.push_field(file_record.get(*c).unwrap_or(""))
+                        }
+                        _ => record.push_field(""),
+                    },
+                }
+            }
+            if let Some(file_record) = file_record {
+                for field in file_record.iter().skip(self.file_columns) {
+                    record.push_field(field);
+                }
+            }
+            record
+        }
+
+        /// Returns true if the cell at `column` of row `pos` has been set by an edit.
+        pub fn is_cell_edited(&self, pos: usize, column: usize) -> bool {
+            match (self.row_id(pos), self.columns.get(column)) {
+                (Some(row), Some(column)) => self.cells.contains_key(&(row, *column)),
+                _ => false,
+            }
+        }
+
+        /// Sets the cell at `column` of row `pos` to `value`. Returns false if there is no such
+        /// cell.
+        pub fn set_cell(&mut self, pos: usize, column: usize, value: String) -> bool {
+            match (self.row_id(pos), self.columns.get(column)) {
+                (Some(row), Some(column)) => {
+                    self.cells.insert((row, *column), value);
+                    self.modified = true;
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        /// Inserts an empty row at `pos`, moving the row there and those after it down.
+        /// A `pos` past the last row appends the row.
+        pub fn insert_row(&mut self, pos: usize) {
+            let id = self.take_id();
+            match self.locate(pos) {
+                Some((i, offset)) => match self.segments[i].clone() {
+                    RowSegment::File(rows) if offset > 0 => {
+                        let split = rows.start + offset;
+                        self.segments.splice(
+                            i..=i,
+                            [
+                                RowSegment::File(rows.start..split),
+                                RowSegment::New(id),
+                                RowSegment::File(split..rows.end),
+                            ],
+                        );
+                    }
+                    _ => self.segments.insert(i, RowSegment::New(id)),
+                },
+                None => self.segments.push(RowSegment::New(id)),
+            }
+            self.rows_changed = true;
+            self.modified = true;
+        }
+
+        /// Deletes the row at `pos`, along with any edits to its cells. Returns false if there is
+        /// no such row.
+        pub fn delete_row(&mut self, pos: usize) -> bool {
+            let (i, offset) = match self.locate(pos) {
+                Some(found) => found,
+                None => return false,
+            };
+            let row = match self.segments[i].clone() {
+                RowSegment::File(rows) => {
+                    let row = rows.start + offset;
+                    let mut remaining = Vec::new();
+                    if row > rows.start {
+                        remaining.push(RowSegment::File(rows.start..row));
+                    }
+                    if row + 1 < rows.end {
+                        remaining.push(RowSegment::File(row + 1..rows.end));
+                    }
+                    self.segments.splice(i..=i, remaining);
+                    RowId::File(row)
+                }
+                RowSegment::New(id) => {
+                    self.segments.remove(i);
+                    RowId::New(id)
+                }
+            };
+            self.cells.retain(|(cell_row, _), _| *cell_row != row);
+            self.rows_changed = true;
+            self.modified = true;
+            true
+        }
+
+        /// Inserts an empty column named `name` at `column`, moving the columns after it right.
+        pub fn insert_column(&mut self, column: usize, name: &str) {
+            let column = column.min(self.columns.len());
+            let id = self.take_id();
+            self.columns.insert(column, ColumnId::New(id));
+            self.headers.insert(column, name.to_string());
+            self.columns_changed = true;
+            self.modified = true;
+        }
+
+        /// Deletes `column`, along with any edits to its cells. Returns false if there is no such
+        /// column.
+        pub fn delete_column(&mut self, column: usize) -> bool {
+            if column >= self.columns.len() {
+                return false;
+            }
+            let id = self.columns.remove(column);
+            self.headers.remove(column);
+            self.cells.retain(|(_, cell_column), _| *cell_column != id);
+            self.columns_changed = true;
+            self.modified = true;
+            true
+        }
+
+        /// Renames `column` to `name`. Returns false if there is no such column.
+        pub fn rename_column(&mut self, column: usize, name: &str) -> bool {
+            match self.headers.get_mut(column) {
+                Some(header) => {
+                    *header = name.to_string();
+                    self.modified = true;
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Writes the edited file, read from `file_path`, to `output_path` in `dialect`.
+        /// The rows are streamed from the file on disk, so only the edits are kept in memory.
+        /// The file is written next to `output_path` and then renamed over it, so the output is
+        /// never left half written, and `output_path` may be `file_path` itself.
+        /// The column names are only written when `has_headers` is set.
+        /// Returns the number of data rows written.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::edit::edit::EditOverlay;
+        /// use csv_viewer::reader::reader::{get_headers_from_file, Dialect};
+        ///
+        /// let path = std::env::temp_dir().join("csv_viewer_edit_save.csv").display().to_string();
+        /// std::fs::write(&path, "name;rank\nzeta;3\n\"a;b\";1\n").unwrap();
+        /// let dialect = Dialect::with_delimiter(';');
+        /// let headers = get_headers_from_file(path.clone(), &dialect, true).unwrap();
+        ///
+        /// let mut edits = EditOverlay::new(&headers, 2);
+        /// edits.set_cell(1, 1, String::from("2"));
+        /// edits.insert_row(0);
+        /// edits.set_cell(0, 0, String::from("new"));
+        /// edits.rename_column(1, "position");
+        /// let rows = edits.save(&path, &path, true, &dialect).unwrap();
+        ///
+        /// assert_eq!(rows, 3);
+        /// assert_eq!(
+        ///     std::fs::read_to_string(&path).unwrap(),
+        ///     "name;position\nnew;\nzeta;3\n\"a;b\";2\n"
+        /// );
+        /// ```
+        pub fn save(
+            &self,
+            file_path: &str,
+            output_path: &str,
+            has_headers: bool,
+            dialect: &Dialect,
+        ) -> Result<usize, Box<dyn Error>> {
+            let temp_path = format!("{}.saving", output_path);
+            match self.write_to(file_path, &temp_path, has_headers, dialect) {
+                Ok(rows) => {
+                    rename(&temp_path, output_path)?;
+                    Ok(rows)
+                }
+                Err(e) => {
+                    let _ = remove_file(&temp_path);
+                    Err(e)
+                }
+            }
+        }
+
+        fn write_to(
+            &self,
+            file_path: &str,
+            output_path: &str,
+            has_headers: bool,
+            dialect: &Dialect,
+        ) -> Result<usize, Box<dyn Error>> {
+            let mut reader = dialect
+                .reader_builder()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(file_path)?;
+            let mut writer = dialect
+                .writer_builder()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(output_path)?;
+
+            let mut record = StringRecord::new();
+            if has_headers {
+                reader.read_record(&mut record)?;
+                writer.write_record(&self.headers)?;
+            }
+            // rows of the file stay in order, so the file is read once from the top
+            let mut next_file_row = 0;
+            let mut rows = 0;
+            for segment in self.segments.iter() {
+                match segment {
+                    RowSegment::File(file_rows) => {
+                        while next_file_row < file_rows.start && reader.read_record(&mut record)? {
+                            next_file_row += 1;
+                        }
+                        for row in file_rows.clone() {
+                            if !reader.read_record(&mut record)? {
+                                return Err(From::from("File has fewer rows than when opened"));
+                            }
+                            next_file_row += 1;
+                            writer.write_record(&self.apply(RowId::File(row), Some(&record)))?;
+                            rows += 1;
+                        }
+                    }
+                    RowSegment::New(id) => {
+                        writer.write_record(&self.apply(RowId::New(*id), None))?;
+                        rows += 1;
+                    }
+                }
+            }
+            writer.flush()?;
+            Ok(rows)
+        }
+
+        /// Returns the segment holding row `pos`, and the offset of the row within it.
+        fn locate(&self, pos: usize) -> Option<(usize, usize)> {
+            let mut start = 0;
+            for (i, segment) in self.segments.iter().enumerate() {
+                let len = segment.len();
+                if pos < start + len {
+                    return Some((i, pos - start));
+                }
+                start += len;
+            }
+            None
+        }
+
+        fn take_id(&mut self) -> usize {
+            let id = self.next_id;
+            self.next_id += 1;
+            id
+        }
+    }
+}
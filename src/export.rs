@@ -0,0 +1,596 @@
+pub mod export {
+    use csv::{QuoteStyle, StringRecord, Writer};
+    use std::error::Error;
+    use std::fs::{canonicalize, File};
+    use std::io::{BufWriter, Write};
+    use std::path::Path;
+
+    use crate::index::index::RowIndex;
+    use crate::reader::reader::{get_records_from_rows, Dialect};
+
+    /// Number of rows of a sorted or edited view read at a time while exporting it.
+    const EXPORT_BLOCK_ROWS: usize = 1024;
+
+    /// Byte order mark optionally written at the start of a CSV export, for programs that only
+    /// detect UTF-8 by it.
+    const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+    /// A file format rows can be exported to.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ExportFormat {
+        /// Delimited text, in the dialect given by the export options.
+        Csv,
+        /// An array of objects keyed by header.
+        Json,
+        /// One JSON object per line.
+        Ndjson,
+        /// A GitHub-flavored Markdown table.
+        Markdown,
+        /// A standalone HTML page holding a table.
+        Html,
+        /// A `CREATE TABLE` statement followed by an `INSERT` statement per row.
+        Sql,
+    }
+
+    /// Every export format, in the order they are listed in the viewer.
+    pub const EXPORT_FORMATS: [ExportFormat; 6] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Ndjson,
+        ExportFormat::Markdown,
+        ExportFormat::Html,
+        ExportFormat::Sql,
+    ];
+
+    impl ExportFormat {
+        /// Returns the name of the format shown to the user.
+        pub fn name(&self) -> &'static str {
+            match self {
+                ExportFormat::Csv => "CSV",
+                ExportFormat::Json => "JSON",
+                ExportFormat::Ndjson => "NDJSON",
+                ExportFormat::Markdown => "Markdown",
+                ExportFormat::Html => "HTML",
+                ExportFormat::Sql => "SQL",
+            }
+        }
+
+        /// Returns the usual file extension of the format, without the dot.
+        pub fn extension(&self) -> &'static str {
+            match self {
+                ExportFormat::Csv => "csv",
+                ExportFormat::Json => "json",
+                ExportFormat::Ndjson => "ndjson",
+                ExportFormat::Markdown => "md",
+                ExportFormat::Html => "html",
+                ExportFormat::Sql => "sql",
+            }
+        }
+    }
+
+    /// Which fields of a CSV export are quoted.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Quoting {
+        /// Every field.
+        Always,
+        /// Only fields holding a delimiter, quote or line ending.
+        Necessary,
+        /// Every field that is not a number, and fields that must be quoted.
+        NonNumeric,
+        /// No field, even if that makes the output ambiguous.
+        Never,
+    }
+
+    impl Quoting {
+        /// Returns the csv quote style writing fields quoted this way.
+        pub fn quote_style(&self) -> QuoteStyle {
+            match self {
+                Quoting::Always => QuoteStyle::Always,
+                Quoting::Necessary => QuoteStyle::Necessary,
+                Quoting::NonNumeric => QuoteStyle::NonNumeric,
+                Quoting::Never => QuoteStyle::Never,
+            }
+        }
+    }
+
+    /// How rows are exported: the format, which columns in which order, and the name of the table
+    /// for the formats that have one. The dialect, quoting and byte order mark only apply to CSV.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ExportOptions {
+        pub format: ExportFormat,
+        // columns exported, in order, or every column when empty
+        pub columns: Vec<usize>,
+        pub table_name: String,
+        pub dialect: Dialect,
+        pub quoting: Quoting,
+        pub bom: bool,
+        // whether a CSV export starts with the header row
+        pub write_headers: bool,
+    }
+
+    impl ExportOptions {
+        /// Returns options exporting every column to `format`, in a table named "data".
+        /// CSV is written in the default dialect, quoting fields only where necessary.
+        pub fn new(format: ExportFormat) -> ExportOptions {
+            ExportOptions {
+                format,
+                columns: Vec::new(),
+                table_name: String::from("data"),
+                dialect: Dialect::default(),
+                quoting: Quoting::Necessary,
+                bom: false,
+                write_headers: true,
+            }
+        }
+    }
+
+    /// Where an `Exporter` writes to. CSV is written through a csv writer, which does the quoting.
+    enum Output<W: Write> {
+        Text(W),
+        Delimited(Box<Writer<W>>),
+    }
+
+    /// Writes records to `W` in one of the export formats, a row at a time, so that nothing but
+    /// the headers is kept in memory. `finish` must be called to close the document.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::export::export::{ExportFormat, ExportOptions, Exporter};
+    ///
+    /// let headers = StringRecord::from(vec!["name", "note"]);
+    /// let export = |format| {
+    ///     let mut exporter =
+    ///         Exporter::new(Vec::new(), &headers, &ExportOptions::new(format)).unwrap();
+    ///     exporter.write_record(&StringRecord::from(vec!["Bo", "a \"b\" | <c>"])).unwrap();
+    ///     exporter.write_record(&StringRecord::from(vec!["O'Neil"])).unwrap();
+    ///     String::from_utf8(exporter.finish().unwrap()).unwrap()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     export(ExportFormat::Ndjson),
+    ///     "{\"name\":\"Bo\",\"note\":\"a \\\"b\\\" | <c>\"}\n{\"name\":\"O'Neil\",\"note\":\"\"}\n"
+    /// );
+    /// assert_eq!(
+    ///     export(ExportFormat::Markdown),
+    ///     "| name | note |\n| --- | --- |\n| Bo | a \"b\" \\| <c> |\n| O'Neil |  |\n"
+    /// );
+    /// assert!(export(ExportFormat::Html).contains("<td>a &quot;b&quot; | &lt;c&gt;</td>"));
+    /// assert!(export(ExportFormat::Sql)
+    ///     .ends_with("INSERT INTO \"data\" (\"name\", \"note\") VALUES ('O''Neil', '');\n"));
+    /// ```
+    ///
+    /// CSV is written in the dialect of the options, here converting to tabs with CRLF line
+    /// endings and every field quoted.
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::export::export::{ExportFormat, ExportOptions, Exporter, Quoting};
+    /// use csv_viewer::reader::reader::{Dialect, LineEnding};
+    ///
+    /// let options = ExportOptions {
+    ///     dialect: Dialect { terminator: LineEnding::Crlf, ..Dialect::with_delimiter('\t') },
+    ///     quoting: Quoting::Always,
+    ///     bom: true,
+    ///     ..ExportOptions::new(ExportFormat::Csv)
+    /// };
+    /// let headers = StringRecord::from(vec!["name", "rank"]);
+    /// let mut exporter = Exporter::new(Vec::new(), &headers, &options).unwrap();
+    /// exporter.write_record(&StringRecord::from(vec!["a;b", "1"])).unwrap();
+    /// let written = exporter.finish().unwrap();
+    /// assert_eq!(written, b"\xEF\xBB\xBF\"name\"\t\"rank\"\r\n\"a;b\"\t\"1\"\r\n");
+    /// ```
+    pub struct Exporter<W: Write> {
+        out: Output<W>,
+        format: ExportFormat,
+        headers: Vec<String>,
+        columns: Vec<usize>,
+        table_name: String,
+        rows: usize,
+    }
+
+    impl<W: Write> Exporter<W> {
+        /// Starts exporting rows with `headers` to `out`, writing the start of the document.
+        pub fn new(
+            mut out: W,
+            headers: &StringRecord,
+            options: &ExportOptions,
+        ) -> Result<Exporter<W>, Box<dyn Error>> {
+            let columns: Vec<usize> = if options.columns.is_empty() {
+                (0..headers.len()).collect()
+            } else {
+                options.columns.clone()
+            };
+            let headers: Vec<String> = columns
+                .iter()
+                .map(|column| headers.get(*column).unwrap_or("").to_string())
+                .collect();
+            let out = match options.format {
+                ExportFormat::Csv => {
+                    if options.bom {
+                        out.write_all(UTF8_BOM)?;
+                    }
+                    let mut writer = options
+                        .dialect
+                        .writer_builder()
+                        .quote_style(options.quoting.quote_style())
+                        .has_headers(false)
+                        .flexible(true)
+                        .from_writer(out);
+                    if options.write_headers {
+                        writer.write_record(&headers)?;
+                    }
+                    Output::Delimited(Box::new(writer))
+                }
+                _ => {
+                    write_text_start(&mut out, options.format, &headers, &options.table_name)?;
+                    Output::Text(out)
+                }
+            };
+            Ok(Exporter {
+                out,
+                format: options.format,
+                headers,
+                columns,
+                table_name: options.table_name.clone(),
+                rows: 0,
+            })
+        }
+
+        /// Writes the exported columns of `record`. Missing cells are exported as empty.
+        pub fn write_record(&mut self, record: &StringRecord) -> Result<(), Box<dyn Error>> {
+            let cells: Vec<&str> = self
+                .columns
+                .iter()
+                .map(|column| record.get(*column).unwrap_or(""))
+                .collect();
+            let out = match &mut self.out {
+                Output::Delimited(writer) => {
+                    writer.write_record(&cells)?;
+                    self.rows += 1;
+                    return Ok(());
+                }
+                Output::Text(out) => out,
+            };
+            match self.format {
+                ExportFormat::Json | ExportFormat::Ndjson => {
+                    if self.format == ExportFormat::Json {
+                        let separator = if self.rows == 0 { "\n  " } else { ",\n  " };
+                        out.write_all(separator.as_bytes())?;
+                    }
+                    let fields: Vec<String> = self
+                        .headers
+                        .iter()
+                        .zip(cells)
+                        .map(|(header, cell)| {
+                            format!("{}:{}", escape_json(header), escape_json(cell))
+                        })
+                        .collect();
+                    write!(out, "{{{}}}", fields.join(","))?;
+                    if self.format == ExportFormat::Ndjson {
+                        writeln!(out)?;
+                    }
+                }
+                ExportFormat::Markdown => {
+                    let cells: Vec<String> = cells.into_iter().map(escape_markdown).collect();
+                    writeln!(out, "| {} |", cells.join(" | "))?;
+                }
+                ExportFormat::Html => {
+                    write!(out, "      <tr>")?;
+                    for cell in cells {
+                        write!(out, "<td>{}</td>", escape_html(cell))?;
+                    }
+                    writeln!(out, "</tr>")?;
+                }
+                ExportFormat::Sql => {
+                    let values: Vec<String> = cells.into_iter().map(quote_sql_string).collect();
+                    writeln!(
+                        out,
+                        "INSERT INTO {} ({}) VALUES ({});",
+                        quote_sql_identifier(&self.table_name),
+                        get_sql_columns(&self.headers),
+                        values.join(", ")
+                    )?;
+                }
+                ExportFormat::Csv => {}
+            }
+            self.rows += 1;
+            Ok(())
+        }
+
+        /// Writes the end of the document and flushes it, returning the writer.
+        pub fn finish(self) -> Result<W, Box<dyn Error>> {
+            let mut out = match self.out {
+                Output::Delimited(writer) => match writer.into_inner() {
+                    Ok(out) => return Ok(out),
+                    Err(e) => return Err(From::from(e.error().to_string())),
+                },
+                Output::Text(out) => out,
+            };
+            match self.format {
+                ExportFormat::Json => {
+                    if self.rows == 0 {
+                        writeln!(out, "]")?;
+                    } else {
+                        writeln!(out, "\n]")?;
+                    }
+                }
+                ExportFormat::Html => {
+                    writeln!(out, "    </tbody>")?;
+                    writeln!(out, "  </table>")?;
+                    writeln!(out, "</body>")?;
+                    writeln!(out, "</html>")?;
+                }
+                ExportFormat::Csv
+                | ExportFormat::Ndjson
+                | ExportFormat::Markdown
+                | ExportFormat::Sql => {}
+            }
+            out.flush()?;
+            Ok(out)
+        }
+
+        /// Returns the number of rows written so far.
+        pub fn rows(&self) -> usize {
+            self.rows
+        }
+    }
+
+    /// Writes what comes before the rows of a document in one of the text formats.
+    fn write_text_start<W: Write>(
+        out: &mut W,
+        format: ExportFormat,
+        headers: &[String],
+        table_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        match format {
+            ExportFormat::Json => write!(out, "[")?,
+            ExportFormat::Markdown => {
+                let headers: Vec<String> = headers.iter().map(|h| escape_markdown(h)).collect();
+                writeln!(out, "| {} |", headers.join(" | "))?;
+                writeln!(out, "|{}", " --- |".repeat(headers.len()))?;
+            }
+            ExportFormat::Html => {
+                writeln!(out, "<!DOCTYPE html>")?;
+                writeln!(out, "<html>")?;
+                writeln!(out, "<head>")?;
+                writeln!(out, "  <meta charset=\"utf-8\">")?;
+                writeln!(out, "  <title>{}</title>", escape_html(table_name))?;
+                writeln!(out, "</head>")?;
+                writeln!(out, "<body>")?;
+                writeln!(out, "  <table>")?;
+                writeln!(out, "    <thead>")?;
+                write!(out, "      <tr>")?;
+                for header in headers.iter() {
+                    write!(out, "<th>{}</th>", escape_html(header))?;
+                }
+                writeln!(out, "</tr>")?;
+                writeln!(out, "    </thead>")?;
+                writeln!(out, "    <tbody>")?;
+            }
+            ExportFormat::Sql => {
+                let columns: Vec<String> = headers
+                    .iter()
+                    .map(|header| format!("  {} TEXT", quote_sql_identifier(header)))
+                    .collect();
+                writeln!(
+                    out,
+                    "CREATE TABLE {} (\n{}\n);",
+                    quote_sql_identifier(table_name),
+                    columns.join(",\n")
+                )?;
+            }
+            ExportFormat::Csv | ExportFormat::Ndjson => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the quoted column names of an `INSERT` statement.
+    fn get_sql_columns(headers: &[String]) -> String {
+        let columns: Vec<String> = headers
+            .iter()
+            .map(|header| quote_sql_identifier(header))
+            .collect();
+        columns.join(", ")
+    }
+
+    /// Exports the data rows of the file at `file_path` to `output_path`, keyed by `headers`.
+    /// When `view` is given only its rows are exported, in its order, read by seeking with the row
+    /// index; otherwise every row is exported in file order. Returns the number of rows written.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::export::export::{export_file, ExportFormat, ExportOptions};
+    /// use csv_viewer::index::index::RowIndex;
+    /// use csv_viewer::reader::reader::{get_headers_from_file, Dialect};
+    ///
+    /// let dialect = Dialect::default();
+    /// let file_path = "tests/test_uspop.csv";
+    /// let headers = get_headers_from_file(file_path.to_string(), &dialect, true).unwrap();
+    /// let index = RowIndex::build(file_path, &dialect, 16).unwrap();
+    /// let output = std::env::temp_dir().join("csv_viewer_export.json").display().to_string();
+    ///
+    /// let options = ExportOptions { columns: vec![1, 0], ..ExportOptions::new(ExportFormat::Json) };
+    /// let view: &[usize] = &[5, 1];
+    /// let rows =
+    ///     export_file(file_path, &output, &options, &headers, Some((&index, view)), true, &dialect)
+    ///         .unwrap();
+    /// assert_eq!(rows, 2);
+    /// assert_eq!(
+    ///     std::fs::read_to_string(&output).unwrap(),
+    ///     "[\n  {\"State\":\"AL\",\"City\":\"Selma\"},\n  {\"State\":\"AK\",\"City\":\"Kenai\"}\n]\n"
+    /// );
+    /// ```
+    pub fn export_file(
+        file_path: &str,
+        output_path: &str,
+        options: &ExportOptions,
+        headers: &StringRecord,
+        view: Option<(&RowIndex, &[usize])>,
+        has_headers: bool,
+        dialect: &Dialect,
+    ) -> Result<usize, Box<dyn Error>> {
+        check_output_path(file_path, output_path)?;
+        let output = BufWriter::new(File::create(output_path)?);
+        let mut exporter = Exporter::new(output, headers, options)?;
+        match view {
+            Some((index, rows)) => {
+                for block in rows.chunks(EXPORT_BLOCK_ROWS) {
+                    let records = get_records_from_rows(
+                        Some(file_path.to_string()),
+                        index,
+                        block,
+                        has_headers,
+                        dialect,
+                    );
+                    for (row, record) in block.iter().zip(records) {
+                        match record {
+                            Some(record) => exporter.write_record(&record)?,
+                            None => return Err(From::from(format!("Cannot read row {}", row + 1))),
+                        }
+                    }
+                }
+            }
+            None => {
+                let mut reader = dialect
+                    .reader_builder()
+                    .has_headers(has_headers)
+                    .flexible(true)
+                    .from_path(file_path)?;
+                let mut record = StringRecord::new();
+                while reader.read_record(&mut record)? {
+                    exporter.write_record(&record)?;
+                }
+            }
+        }
+        let rows = exporter.rows();
+        exporter.finish()?;
+        Ok(rows)
+    }
+
+    /// Exports `total` rows to `output_path`, keyed by `headers`, reading them in blocks with
+    /// `read`, which is given the first row of a block and the number of rows in it. This exports
+    /// rows that are not simply those on disk, e.g. with edits made in the viewer applied.
+    /// The rows are read from `file_path` while exporting, so it is not overwritten.
+    /// Returns the number of rows written.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::export::export::{export_blocks, ExportFormat, ExportOptions};
+    ///
+    /// let headers = StringRecord::from(vec!["n"]);
+    /// let output = std::env::temp_dir().join("csv_viewer_export_blocks.md").display().to_string();
+    /// let options = ExportOptions::new(ExportFormat::Markdown);
+    /// let read = |start: usize, n: usize| {
+    ///     (start..start + n).map(|row| StringRecord::from(vec![row.to_string()])).collect()
+    /// };
+    /// let rows = export_blocks("tests/test_uspop.csv", &output, &options, &headers, 2, read).unwrap();
+    /// assert_eq!(rows, 2);
+    /// assert_eq!(std::fs::read_to_string(&output).unwrap(), "| n |\n| --- |\n| 0 |\n| 1 |\n");
+    /// ```
+    pub fn export_blocks<F>(
+        file_path: &str,
+        output_path: &str,
+        options: &ExportOptions,
+        headers: &StringRecord,
+        total: usize,
+        mut read: F,
+    ) -> Result<usize, Box<dyn Error>>
+    where
+        F: FnMut(usize, usize) -> Vec<StringRecord>,
+    {
+        check_output_path(file_path, output_path)?;
+        let output = BufWriter::new(File::create(output_path)?);
+        let mut exporter = Exporter::new(output, headers, options)?;
+        let mut start = 0;
+        while start < total {
+            let n = EXPORT_BLOCK_ROWS.min(total - start);
+            let records = read(start, n);
+            if records.len() < n {
+                return Err(From::from(format!(
+                    "Cannot read row {}",
+                    start + records.len() + 1
+                )));
+            }
+            for record in records.iter() {
+                exporter.write_record(record)?;
+            }
+            start += n;
+        }
+        let rows = exporter.rows();
+        exporter.finish()?;
+        Ok(rows)
+    }
+
+    /// Fails if `output_path` is the file at `file_path`, as writing the output would truncate
+    /// the file still being read.
+    fn check_output_path(file_path: &str, output_path: &str) -> Result<(), Box<dyn Error>> {
+        if let (Ok(input), Ok(output)) = (canonicalize(file_path), canonicalize(output_path)) {
+            if input == output {
+                return Err(From::from("Cannot export into the file being read"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the name of a table exported to `output_path`, taken from the file name.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::export::export::get_table_name;
+    ///
+    /// assert_eq!(get_table_name("/tmp/us pop.sql"), "us pop");
+    /// assert_eq!(get_table_name(""), "data");
+    /// ```
+    pub fn get_table_name(output_path: &str) -> String {
+        match Path::new(output_path).file_stem() {
+            Some(stem) if !stem.is_empty() => stem.to_string_lossy().to_string(),
+            _ => String::from("data"),
+        }
+    }
+
+    /// Returns `text` as a quoted JSON string.
+    fn escape_json(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len() + 2);
+        escaped.push('"');
+        for c in text.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    }
+
+    /// Escapes `text` for a Markdown table cell, which cannot hold a pipe or a line break.
+    fn escape_markdown(text: &str) -> String {
+        text.replace('\\', "\\\\")
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace(['\n', '\r'], "<br>")
+    }
+
+    /// Escapes the characters of `text` with a meaning in HTML.
+    fn escape_html(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    /// Returns `name` as a quoted SQL identifier.
+    fn quote_sql_identifier(name: &str) -> String {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+
+    /// Returns `text` as a quoted SQL string literal.
+    fn quote_sql_string(text: &str) -> String {
+        format!("'{}'", text.replace('\'', "''"))
+    }
+}
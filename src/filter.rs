@@ -0,0 +1,473 @@
+pub mod filter {
+    use csv::StringRecord;
+    use regex::Regex;
+    use std::error::Error;
+    use std::iter::Peekable;
+    use std::str::Chars;
+
+    use crate::reader::reader::Dialect;
+
+    /// A condition on the cells of a row, parsed from a filter query such as
+    /// `State == "AL" and Population > 10000`.
+    ///
+    /// Columns are named by their header, quoted in backticks if the name has spaces or is a
+    /// keyword, e.g. `` `Zip Code` ``. Values are numbers or double quoted strings. Supported are
+    /// - comparisons: `==`, `!=`, `<`, `<=`, `>`, `>=`. These compare as numbers when both sides
+    ///   are numbers, and as text otherwise.
+    /// - text tests: `contains`, `startswith`, `endswith`, and `matches` for a regular expression.
+    /// - `is empty` and `is not empty`.
+    /// - `and`, `or`, `not` and parentheses.
+    #[derive(Debug, Clone)]
+    pub enum Filter {
+        And(Box<Filter>, Box<Filter>),
+        Or(Box<Filter>, Box<Filter>),
+        Not(Box<Filter>),
+        Compare {
+            column: usize,
+            op: CompareOp,
+            value: String,
+        },
+        Matches {
+            column: usize,
+            regex: Regex,
+        },
+        IsEmpty {
+            column: usize,
+        },
+    }
+
+    /// The operators comparing a cell to a value.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum CompareOp {
+        Equal,
+        NotEqual,
+        Less,
+        LessOrEqual,
+        Greater,
+        GreaterOrEqual,
+        Contains,
+        StartsWith,
+        EndsWith,
+    }
+
+    impl Filter {
+        /// Parses the filter query `text`, resolving column names against `headers`.
+        ///
+        /// # Example
+        /// ```
+        /// use csv::StringRecord;
+        /// use csv_viewer::filter::filter::Filter;
+        ///
+        /// let headers = StringRecord::from(vec!["City", "State", "Population"]);
+        /// let filter = Filter::parse(r#"State == "AL" and Population > 10000"#, &headers).unwrap();
+        /// assert!(filter.matches(&StringRecord::from(vec!["Selma", "AL", "18980"])));
+        /// assert!(!filter.matches(&StringRecord::from(vec!["Oakman", "AL", ""])));
+        ///
+        /// let filter = Filter::parse(r#"not (City startswith "S" or Population is empty)"#, &headers).unwrap();
+        /// assert!(filter.matches(&StringRecord::from(vec!["Kenai", "AK", "7610"])));
+        /// assert!(!filter.matches(&StringRecord::from(vec!["Sandfort", "AL", "9"])));
+        ///
+        /// assert!(Filter::parse("County == 1", &headers).is_err());
+        /// assert!(Filter::parse("State ==", &headers).is_err());
+        /// ```
+        pub fn parse(text: &str, headers: &StringRecord) -> Result<Filter, Box<dyn Error>> {
+            let tokens = tokenize(text)?;
+            let mut parser = Parser {
+                tokens,
+                pos: 0,
+                headers,
+            };
+            let filter = parser.parse_or()?;
+            match parser.peek() {
+                None => Ok(filter),
+                Some(token) => Err(From::from(format!("Unexpected {}", token))),
+            }
+        }
+
+        /// Returns true if `record` satisfies the filter. A missing cell is treated as empty.
+        pub fn matches(&self, record: &StringRecord) -> bool {
+            match self {
+                Filter::And(a, b) => a.matches(record) && b.matches(record),
+                Filter::Or(a, b) => a.matches(record) || b.matches(record),
+                Filter::Not(a) => !a.matches(record),
+                Filter::Compare { column, op, value } => {
+                    compare_cell(record.get(*column).unwrap_or(""), *op, value)
+                }
+                Filter::Matches { column, regex } => {
+                    regex.is_match(record.get(*column).unwrap_or(""))
+                }
+                Filter::IsEmpty { column } => record.get(*column).unwrap_or("").trim().is_empty(),
+            }
+        }
+    }
+
+    /// Returns true if `cell` compares to `value` as `op` asks.
+    fn compare_cell(cell: &str, op: CompareOp, value: &str) -> bool {
+        let numbers = match (cell.trim().parse::<f64>(), value.parse::<f64>()) {
+            (Ok(a), Ok(b)) => Some((a, b)),
+            _ => None,
+        };
+        match op {
+            CompareOp::Equal => match numbers {
+                Some((a, b)) => a == b,
+                None => cell == value,
+            },
+            CompareOp::NotEqual => match numbers {
+                Some((a, b)) => a != b,
+                None => cell != value,
+            },
+            CompareOp::Less
+            | CompareOp::LessOrEqual
+            | CompareOp::Greater
+            | CompareOp::GreaterOrEqual => {
+                let ordering = match numbers {
+                    Some((a, b)) => a.partial_cmp(&b),
+                    // a number can't be compared to text, such as an empty cell
+                    None if value.parse::<f64>().is_ok() => None,
+                    None => Some(cell.cmp(value)),
+                };
+                match ordering {
+                    Some(ordering) => match op {
+                        CompareOp::Less => ordering.is_lt(),
+                        CompareOp::LessOrEqual => ordering.is_le(),
+                        CompareOp::Greater => ordering.is_gt(),
+                        _ => ordering.is_ge(),
+                    },
+                    None => false,
+                }
+            }
+            CompareOp::Contains => cell.contains(value),
+            CompareOp::StartsWith => cell.starts_with(value),
+            CompareOp::EndsWith => cell.ends_with(value),
+        }
+    }
+
+    /// A token of a filter query.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        /// A column name or keyword. Backtick quoted names are never keywords.
+        Word(String, bool),
+        Str(String),
+        Number(String),
+        Symbol(&'static str),
+    }
+
+    impl std::fmt::Display for Token {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Token::Word(word, _) => write!(f, "`{}`", word),
+                Token::Str(text) => write!(f, "{:?}", text),
+                Token::Number(number) => write!(f, "{}", number),
+                Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+            }
+        }
+    }
+
+    /// Symbols recognised by the tokenizer, longest first so `<=` is not read as `<`.
+    const SYMBOLS: [&str; 8] = ["==", "!=", "<=", ">=", "<", ">", "(", ")"];
+
+    /// Splits a filter query into tokens.
+    fn tokenize(text: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '"' || c == '`' {
+                chars.next();
+                let quoted = read_quoted(&mut chars, c)?;
+                if c == '"' {
+                    tokens.push(Token::Str(quoted));
+                } else {
+                    tokens.push(Token::Word(quoted, true));
+                }
+            } else if c.is_ascii_digit() || c == '-' || c == '.' {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_digit() || c == '.' || (c == '-' && number.is_empty()) {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if number.parse::<f64>().is_err() {
+                    return Err(From::from(format!("Invalid number {}", number)));
+                }
+                tokens.push(Token::Number(number));
+            } else if c.is_alphanumeric() || c == '_' {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Word(word, false));
+            } else {
+                let rest: String = chars.clone().take(2).collect();
+                match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                    Some(symbol) => {
+                        for _ in 0..symbol.len() {
+                            chars.next();
+                        }
+                        tokens.push(Token::Symbol(symbol));
+                    }
+                    None => return Err(From::from(format!("Unexpected character '{}'", c))),
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Reads a quoted string up to the closing `quote`. A doubled quote or a backslash escapes a
+    /// quote inside the string.
+    fn read_quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String, Box<dyn Error>> {
+        let mut text = String::new();
+        while let Some(c) = chars.next() {
+            if (c == '\\' || c == quote) && chars.peek() == Some(&quote) {
+                text.push(quote);
+                chars.next();
+            } else if c == quote {
+                return Ok(text);
+            } else {
+                text.push(c);
+            }
+        }
+        Err(From::from(format!("Missing closing {}", quote)))
+    }
+
+    /// A recursive descent parser over the tokens of a filter query.
+    struct Parser<'a> {
+        tokens: Vec<Token>,
+        pos: usize,
+        headers: &'a StringRecord,
+    }
+
+    impl Parser<'_> {
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.pos)
+        }
+
+        fn next(&mut self) -> Option<Token> {
+            let token = self.tokens.get(self.pos).cloned();
+            self.pos += 1;
+            token
+        }
+
+        /// Consumes the next token if it is the unquoted keyword `keyword`.
+        fn take_keyword(&mut self, keyword: &str) -> bool {
+            match self.peek() {
+                Some(Token::Word(word, false)) if word.eq_ignore_ascii_case(keyword) => {
+                    self.pos += 1;
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        fn parse_or(&mut self) -> Result<Filter, Box<dyn Error>> {
+            let mut filter = self.parse_and()?;
+            while self.take_keyword("or") {
+                filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+            }
+            Ok(filter)
+        }
+
+        fn parse_and(&mut self) -> Result<Filter, Box<dyn Error>> {
+            let mut filter = self.parse_not()?;
+            while self.take_keyword("and") {
+                filter = Filter::And(Box::new(filter), Box::new(self.parse_not()?));
+            }
+            Ok(filter)
+        }
+
+        fn parse_not(&mut self) -> Result<Filter, Box<dyn Error>> {
+            if self.take_keyword("not") {
+                return Ok(Filter::Not(Box::new(self.parse_not()?)));
+            }
+            if self.peek() == Some(&Token::Symbol("(")) {
+                self.pos += 1;
+                let filter = self.parse_or()?;
+                return match self.next() {
+                    Some(Token::Symbol(")")) => Ok(filter),
+                    _ => Err(From::from("Missing closing ')'")),
+                };
+            }
+            self.parse_condition()
+        }
+
+        /// Parses a single condition on a column, e.g. `Population > 10000`.
+        fn parse_condition(&mut self) -> Result<Filter, Box<dyn Error>> {
+            let column = match self.next() {
+                Some(Token::Word(name, _)) => match self.headers.iter().position(|h| h == name) {
+                    Some(column) => column,
+                    None => return Err(From::from(format!("Unknown column `{}`", name))),
+                },
+                Some(token) => {
+                    return Err(From::from(format!("Expected a column, found {}", token)))
+                }
+                None => return Err(From::from("Expected a column")),
+            };
+
+            if self.take_keyword("is") {
+                let negated = self.take_keyword("not");
+                if !self.take_keyword("empty") {
+                    return Err(From::from("Expected 'empty' after 'is'"));
+                }
+                let filter = Filter::IsEmpty { column };
+                return Ok(if negated {
+                    Filter::Not(Box::new(filter))
+                } else {
+                    filter
+                });
+            }
+
+            let op = match self.next() {
+                Some(Token::Symbol("==")) => CompareOp::Equal,
+                Some(Token::Symbol("!=")) => CompareOp::NotEqual,
+                Some(Token::Symbol("<")) => CompareOp::Less,
+                Some(Token::Symbol("<=")) => CompareOp::LessOrEqual,
+                Some(Token::Symbol(">")) => CompareOp::Greater,
+                Some(Token::Symbol(">=")) => CompareOp::GreaterOrEqual,
+                Some(Token::Word(word, false)) if word.eq_ignore_ascii_case("contains") => {
+                    CompareOp::Contains
+                }
+                Some(Token::Word(word, false)) if word.eq_ignore_ascii_case("startswith") => {
+                    CompareOp::StartsWith
+                }
+                Some(Token::Word(word, false)) if word.eq_ignore_ascii_case("endswith") => {
+                    CompareOp::EndsWith
+                }
+                Some(Token::Word(word, false)) if word.eq_ignore_ascii_case("matches") => {
+                    return match self.next() {
+                        Some(Token::Str(pattern)) => Ok(Filter::Matches {
+                            column,
+                            regex: Regex::new(&pattern)?,
+                        }),
+                        _ => Err(From::from("Expected a quoted pattern after 'matches'")),
+                    };
+                }
+                Some(token) => {
+                    return Err(From::from(format!(
+                        "Expected a comparison, found {}",
+                        token
+                    )))
+                }
+                None => return Err(From::from("Expected a comparison")),
+            };
+
+            match self.next() {
+                Some(Token::Str(value)) | Some(Token::Number(value)) => {
+                    Ok(Filter::Compare { column, op, value })
+                }
+                _ => Err(From::from("Expected a number or quoted value")),
+            }
+        }
+    }
+
+    /// The data rows of a file that match a filter, in file order.
+    /// Row numbers count data rows from 0, not including the header.
+    #[derive(Debug, Clone)]
+    pub struct RowFilter {
+        pub text: String,
+        rows: Vec<usize>,
+        total_rows: usize,
+    }
+
+    impl RowFilter {
+        /// Reads the file at `file_path` record by record, keeping the numbers of the rows that
+        /// match the filter query `text`. `headers` are used to resolve column names.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::filter::filter::RowFilter;
+        /// use csv_viewer::reader::reader::{get_headers_from_file, Dialect};
+        ///
+        /// let dialect = Dialect::default();
+        /// let headers = get_headers_from_file(String::from("tests/test_uspop.csv"), &dialect, true).unwrap();
+        /// let filter = RowFilter::build(
+        ///     "tests/test_uspop.csv", r#"State == "AL" and Population > 10000"#, &headers, true, &dialect,
+        /// ).unwrap();
+        /// // Selma appears twice in the file
+        /// assert_eq!(filter.len(), 2);
+        /// assert_eq!(filter.total_rows(), 201);
+        /// assert_eq!(filter.get_range(0, 10), &[5, 106]);
+        /// assert_eq!(filter.position_of(106), Some(1));
+        /// ```
+        pub fn build(
+            file_path: &str,
+            text: &str,
+            headers: &StringRecord,
+            has_headers: bool,
+            dialect: &Dialect,
+        ) -> Result<RowFilter, Box<dyn Error>> {
+            let filter = Filter::parse(text, headers)?;
+            let mut reader = dialect
+                .reader_builder()
+                .has_headers(has_headers)
+                .flexible(true)
+                .from_path(file_path)?;
+
+            let mut rows: Vec<usize> = Vec::new();
+            let mut record = StringRecord::new();
+            let mut total_rows = 0;
+            loop {
+                match reader.read_record(&mut record) {
+                    Ok(false) => break,
+                    Ok(true) => {
+                        if filter.matches(&record) {
+                            rows.push(total_rows);
+                        }
+                    }
+                    Err(e) => println!("Error: Cannot Read Record: {}", e),
+                }
+                total_rows += 1;
+            }
+            Ok(RowFilter {
+                text: text.to_string(),
+                rows,
+                total_rows,
+            })
+        }
+
+        /// Returns the number of rows matching the filter.
+        pub fn len(&self) -> usize {
+            self.rows.len()
+        }
+
+        /// Returns true if no rows match the filter.
+        pub fn is_empty(&self) -> bool {
+            self.rows.is_empty()
+        }
+
+        /// Returns the number of rows in the file, matching or not.
+        pub fn total_rows(&self) -> usize {
+            self.total_rows
+        }
+
+        /// Returns the file row shown at `pos` of the filtered view.
+        pub fn get(&self, pos: usize) -> Option<usize> {
+            self.rows.get(pos).copied()
+        }
+
+        /// Returns the file rows shown at `pos` and up to `n` rows after it in the filtered view.
+        pub fn get_range(&self, pos: usize, n: usize) -> &[usize] {
+            let start = pos.min(self.rows.len());
+            let end = pos.saturating_add(n).min(self.rows.len());
+            &self.rows[start..end]
+        }
+
+        /// Returns the position in the filtered view of file row `row`, if it matches the filter.
+        pub fn position_of(&self, row: usize) -> Option<usize> {
+            self.rows.binary_search(&row).ok()
+        }
+
+        /// Returns true if file row `row` matches the filter.
+        pub fn contains(&self, row: usize) -> bool {
+            self.position_of(row).is_some()
+        }
+    }
+}
@@ -1,56 +1,506 @@
 pub mod find {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use csv::{Reader, StringRecord};
+    use regex::{Regex, RegexBuilder};
+    use std::error::Error;
+    use std::fs::{self, File};
+    use std::ops::Range;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+    use std::sync::Arc;
+    use std::thread;
 
-    /// Finds all matches and returns a vector of the indices of rows with matching strings.
+    use crate::reader::reader::Dialect;
+
+    /// The text to search for, and the options controlling how it is matched against cells.
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct FindQuery {
+        pub text: String,
+        pub case_insensitive: bool,
+        /// Only match the text where it is not part of a longer word.
+        pub whole_word: bool,
+        /// Treat the text as a regular expression rather than literal text.
+        pub regex: bool,
+        /// Only match cells that the text matches from start to end.
+        pub whole_cell: bool,
+        /// Indices of the columns to search. All columns are searched when empty.
+        pub columns: Vec<usize>,
+    }
+
+    /// A match of a query within a cell. `range` is the byte range of the match within the cell.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct CellMatch {
+        pub row: usize,
+        pub column: usize,
+        pub range: Range<usize>,
+    }
+
+    impl FindQuery {
+        /// Returns a case-sensitive query for the literal `text`.
+        pub fn new(text: &str) -> FindQuery {
+            FindQuery {
+                text: text.to_string(),
+                ..Default::default()
+            }
+        }
+
+        /// Compiles the query into a regular expression that matches what it searches for.
+        /// Fails if the query is a regular expression that isn't valid.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::find::find::FindQuery;
+        ///
+        /// let query = FindQuery { case_insensitive: true, whole_word: true, ..FindQuery::new("al") };
+        /// let regex = query.compile().unwrap();
+        /// assert!(regex.is_match("AL"));
+        /// assert!(!regex.is_match("Alice"));
+        ///
+        /// let query = FindQuery { regex: true, ..FindQuery::new("(unclosed") };
+        /// assert!(query.compile().is_err());
+        /// ```
+        pub fn compile(&self) -> Result<Regex, regex::Error> {
+            let mut pattern = if self.regex {
+                self.text.clone()
+            } else {
+                regex::escape(&self.text)
+            };
+            if self.whole_word {
+                pattern = format!(r"\b(?:{})\b", pattern);
+            }
+            if self.whole_cell {
+                pattern = format!(r"^(?:{})$", pattern);
+            }
+            RegexBuilder::new(&pattern)
+                .case_insensitive(self.case_insensitive)
+                .build()
+        }
+
+        /// Returns true if `column` is one of the columns the query searches.
+        pub fn searches_column(&self, column: usize) -> bool {
+            self.columns.is_empty() || self.columns.contains(&column)
+        }
+    }
+
+    /// Finds all matches and returns a vector of the indices of rows with a cell matching `query`.
+    /// Row indices count data rows only, so if the file has headers the header row is not
+    /// searched and the first row after it is row 0.
     ///# Example
-    /// This test creates a CSV file with 5 rows, and then calls the `find_matching_rows` function
+    /// This test creates a CSV file with 5 rows and no header, and then calls the `find_matching_rows` function
     ///to search for rows that contain the string "30". The function should return the indices of the
     /// first and last rows. This is because they both contain the string "30".
     /// ```
-    /// use csv_viewer::find::find::find_matching_rows;
+    /// use csv_viewer::find::find::{find_matching_rows, FindQuery};
+    /// use csv_viewer::reader::reader::Dialect;
     ///
     /// // "Alice,30\nBob,35\nCharlie,25\nDave,40\nEve,30\n"
     /// let file_path = "tests/test_find_matching.csv";
     /// let contents = "Alice,30\nBob,35\nCharlie,25\nDave,40\nEve,30\n";
     /// std::fs::write(file_path, contents).unwrap();
+    /// let file_path = Some(file_path.to_owned());
+    /// let dialect = Dialect::default();
     ///
-    /// let search_str = "30";
-    /// let result = find_matching_rows(Some(file_path.to_owned()), search_str.to_owned(), true);
+    /// let query = FindQuery::new("30");
+    /// let result = find_matching_rows(file_path.clone(), &query, false, &dialect).unwrap();
     ///
     /// // matching row indices should be 0 and 4.
     /// assert_eq!(result, vec![0, 4]);
+    ///
+    /// // treating "Alice,30" as the header, "Eve,30" is the only match, at row 3.
+    /// let result = find_matching_rows(file_path.clone(), &query, true, &dialect).unwrap();
+    /// assert_eq!(result, vec![3]);
+    ///
+    /// // names ending in "e", ignoring case, found with a regular expression
+    /// let query = FindQuery { regex: true, whole_cell: true, case_insensitive: true, ..FindQuery::new("[a-z]+E") };
+    /// let result = find_matching_rows(file_path.clone(), &query, false, &dialect).unwrap();
+    /// assert_eq!(result, vec![0, 2, 3, 4]);
+    ///
+    /// // "3" is only part of a cell, so finds nothing when matching whole cells
+    /// let query = FindQuery { whole_cell: true, ..FindQuery::new("3") };
+    /// assert!(find_matching_rows(file_path, &query, false, &dialect).unwrap().is_empty());
     /// ```
-
     pub fn find_matching_rows(
         file_path: Option<String>,
-        search_str: String,
+        query: &FindQuery,
         has_headers: bool,
-    ) -> Vec<usize> {
+        dialect: &Dialect,
+    ) -> Result<Vec<usize>, Box<dyn Error>> {
+        let regex = query.compile()?;
         let mut matched_rows: Vec<usize> = Vec::new();
-        let file = File::open(file_path.unwrap()).unwrap();
-        let reader = BufReader::new(file);
+        // the header row is not part of the data
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(has_headers)
+            .flexible(true)
+            .from_path(file_path.unwrap())?;
+
+        // iterates through the file finding matching records,
+        // adding index of the row to a vector.
+        for (row_pos, result) in reader.records().enumerate() {
+            match result {
+                Ok(record) => {
+                    if record_matches(&record, &regex, query) {
+                        matched_rows.push(row_pos);
+                    }
+                }
+                Err(e) => println!("Error: Cannot Read Record: {}", e),
+            }
+        }
+        Ok(matched_rows)
+    }
+
+    /// Finds every match of `query` within the cells of the file, in the columns it is scoped to.
+    /// Rows are numbered the same way as in `find_matching_rows`.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::{find_matching_cells, CellMatch, FindQuery};
+    /// use csv_viewer::reader::reader::Dialect;
+    ///
+    /// let file_path = Some(String::from("tests/test_uspop.csv"));
+    /// let query = FindQuery { case_insensitive: true, columns: vec![0], ..FindQuery::new("ak") };
+    /// let cells = find_matching_cells(file_path, &query, true, &Dialect::default()).unwrap();
+    ///
+    /// // "Oakman" and "Shadow Oaks Addition", but not "AK" in the State column
+    /// assert_eq!(cells[0], CellMatch { row: 2, column: 0, range: 1..3 });
+    /// assert_eq!(cells[1], CellMatch { row: 6, column: 0, range: 8..10 });
+    /// assert!(cells.iter().all(|cell| cell.column == 0));
+    /// ```
+    pub fn find_matching_cells(
+        file_path: Option<String>,
+        query: &FindQuery,
+        has_headers: bool,
+        dialect: &Dialect,
+    ) -> Result<Vec<CellMatch>, Box<dyn Error>> {
+        let regex = query.compile()?;
+        let mut matched_cells: Vec<CellMatch> = Vec::new();
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(has_headers)
+            .flexible(true)
+            .from_path(file_path.unwrap())?;
+
+        let never_cancelled = AtomicBool::new(false);
+        scan_cells(&mut reader, &regex, query, &never_cancelled, |cells, _| {
+            matched_cells.extend(cells);
+            true
+        });
+        Ok(matched_cells)
+    }
+
+    /// Number of records scanned between each batch of matches sent back by a background search.
+    const SEARCH_BATCH_RECORDS: usize = 4096;
+
+    /// Reads every record from `reader`, passing the matches found to `on_batch` every
+    /// `SEARCH_BATCH_RECORDS` records along with the number of bytes read so far.
+    /// Stops early once `cancel` is set, or `on_batch` returns false.
+    fn scan_cells<F>(
+        reader: &mut Reader<File>,
+        regex: &Regex,
+        query: &FindQuery,
+        cancel: &AtomicBool,
+        mut on_batch: F,
+    ) where
+        F: FnMut(Vec<CellMatch>, u64) -> bool,
+    {
+        let mut batch: Vec<CellMatch> = Vec::new();
+        let mut record = StringRecord::new();
         let mut row_pos = 0;
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            match reader.read_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => batch.extend(find_in_record(&record, row_pos, regex, query)),
+                Err(e) => println!("Error: Cannot Read Record: {}", e),
+            }
+            row_pos += 1;
+            if row_pos % SEARCH_BATCH_RECORDS == 0
+                && !on_batch(std::mem::take(&mut batch), reader.position().byte())
+            {
+                return;
+            }
+        }
+        on_batch(batch, reader.position().byte());
+    }
+
+    /// Messages sent from a background search to the thread that started it.
+    enum SearchMessage {
+        /// Matches found since the last message, and the number of bytes scanned so far.
+        Matches(Vec<CellMatch>, u64),
+        Finished,
+    }
+
+    /// A search running on a worker thread, streaming its matches back as they are found.
+    pub struct BackgroundSearch {
+        receiver: Receiver<SearchMessage>,
+        cancel: Arc<AtomicBool>,
+        bytes_scanned: u64,
+        total_bytes: u64,
+        finished: bool,
+    }
 
-        for line in reader.lines() {
-            let row = line.unwrap();
-            let row_bytes = row.as_bytes();
+    impl BackgroundSearch {
+        /// Starts searching the file at `file_path` for `query` on a new thread.
+        /// The query is compiled and the file opened before returning, so that an invalid regular
+        /// expression or a missing file is reported straight away.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::find::find::{find_matching_cells, BackgroundSearch, FindQuery};
+        /// use csv_viewer::reader::reader::Dialect;
+        ///
+        /// let file_path = String::from("tests/test_uspop.csv");
+        /// let query = FindQuery::new("AL");
+        /// let dialect = Dialect::default();
+        ///
+        /// let mut search = BackgroundSearch::start(file_path.clone(), &query, true, &dialect).unwrap();
+        /// let mut cells = Vec::new();
+        /// while !search.is_finished() {
+        ///     cells.extend(search.poll());
+        ///     std::thread::sleep(std::time::Duration::from_millis(1));
+        /// }
+        /// cells.extend(search.poll());
+        ///
+        /// assert_eq!(cells, find_matching_cells(Some(file_path), &query, true, &dialect).unwrap());
+        /// assert_eq!(search.progress(), 1.0);
+        /// ```
+        pub fn start(
+            file_path: String,
+            query: &FindQuery,
+            has_headers: bool,
+            dialect: &Dialect,
+        ) -> Result<BackgroundSearch, Box<dyn Error>> {
+            let regex = query.compile()?;
+            let total_bytes = fs::metadata(&file_path)?.len();
+            let mut reader = dialect
+                .reader_builder()
+                .has_headers(has_headers)
+                .flexible(true)
+                .from_path(file_path)?;
 
-            // iterates through the file finding matching lines,
-            // adding index of the row to a vector.
-            for window in row_bytes.windows(search_str.as_bytes().len()) {
-                if window == search_str.as_bytes() {
-                    matched_rows.push(row_pos);
-                    break;
+            let (sender, receiver) = channel();
+            let cancel = Arc::new(AtomicBool::new(false));
+            let worker_cancel = Arc::clone(&cancel);
+            let query = query.clone();
+            thread::spawn(move || {
+                run_search(&mut reader, &regex, &query, &worker_cancel, sender);
+            });
+
+            Ok(BackgroundSearch {
+                receiver,
+                cancel,
+                bytes_scanned: 0,
+                total_bytes,
+                finished: false,
+            })
+        }
+
+        /// Returns the matches that have arrived since the last poll, without waiting for more.
+        pub fn poll(&mut self) -> Vec<CellMatch> {
+            let mut cells: Vec<CellMatch> = Vec::new();
+            loop {
+                match self.receiver.try_recv() {
+                    Ok(SearchMessage::Matches(batch, bytes_scanned)) => {
+                        cells.extend(batch);
+                        self.bytes_scanned = bytes_scanned;
+                    }
+                    Ok(SearchMessage::Finished) | Err(TryRecvError::Disconnected) => {
+                        self.finished = true;
+                        break;
+                    }
+                    Err(TryRecvError::Empty) => break,
                 }
             }
-            row_pos = row_pos + 1;
+            cells
         }
-        matched_rows
+
+        /// Asks the worker thread to stop searching. Matches already found are still returned by
+        /// `poll`.
+        pub fn cancel(&self) {
+            self.cancel.store(true, Ordering::Relaxed);
+        }
+
+        /// Returns true once the worker thread has finished or been cancelled, and every match it
+        /// found has been sent.
+        pub fn is_finished(&self) -> bool {
+            self.finished
+        }
+
+        /// Returns the fraction of the file searched so far, from 0 to 1.
+        pub fn progress(&self) -> f32 {
+            if self.total_bytes == 0 {
+                return 1.0;
+            }
+            self.bytes_scanned as f32 / self.total_bytes as f32
+        }
+
+        /// Returns the number of bytes of the file searched so far, and the size of the file.
+        pub fn bytes(&self) -> (u64, u64) {
+            (self.bytes_scanned, self.total_bytes)
+        }
+    }
+
+    impl Drop for BackgroundSearch {
+        /// Stops the worker thread, so that a search replaced by a new one doesn't keep running.
+        fn drop(&mut self) {
+            self.cancel();
+        }
+    }
+
+    /// Runs on the worker thread of a `BackgroundSearch`, sending batches of matches back as they
+    /// are found. Stops early if the search is dropped, as there is no one left to send to.
+    fn run_search(
+        reader: &mut Reader<File>,
+        regex: &Regex,
+        query: &FindQuery,
+        cancel: &AtomicBool,
+        sender: Sender<SearchMessage>,
+    ) {
+        scan_cells(reader, regex, query, cancel, |cells, bytes_scanned| {
+            sender
+                .send(SearchMessage::Matches(cells, bytes_scanned))
+                .is_ok()
+        });
+        // the search may already have been dropped, in which case there is no one to tell
+        let _ = sender.send(SearchMessage::Finished);
+    }
+
+    /// Returns true if any cell of `record` in the columns searched by `query` matches `regex`,
+    /// the compiled form of `query`.
+    pub fn record_matches(record: &StringRecord, regex: &Regex, query: &FindQuery) -> bool {
+        record
+            .iter()
+            .enumerate()
+            .any(|(column, cell)| query.searches_column(column) && regex.is_match(cell))
     }
 
-    /// Returns the index of the next matching string, stored at within the vector
-    /// using the index i for the element within the vec.
+    /// Returns the matches of `regex`, the compiled form of `query`, within the cells of `record`
+    /// in the columns searched by `query`. `row` is the row number given to the matches.
+    /// Empty matches are skipped, unless the cell itself is empty, so that a query such as `^$`
+    /// can find empty cells.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::find::find::{find_in_record, FindQuery};
+    ///
+    /// let query = FindQuery { case_insensitive: true, ..FindQuery::new("a") };
+    /// let record = StringRecord::from(vec!["Alabama", "AL"]);
+    /// let cells = find_in_record(&record, 7, &query.compile().unwrap(), &query);
+    /// assert_eq!(cells.len(), 5);
+    /// assert_eq!(cells[4].row, 7);
+    /// assert_eq!(cells[4].column, 1);
+    /// ```
+    pub fn find_in_record(
+        record: &StringRecord,
+        row: usize,
+        regex: &Regex,
+        query: &FindQuery,
+    ) -> Vec<CellMatch> {
+        let mut matched_cells: Vec<CellMatch> = Vec::new();
+        for (column, cell) in record.iter().enumerate() {
+            if !query.searches_column(column) {
+                continue;
+            }
+            for found in regex.find_iter(cell) {
+                if found.start() < found.end() || cell.is_empty() {
+                    matched_cells.push(CellMatch {
+                        row,
+                        column,
+                        range: found.range(),
+                    });
+                }
+            }
+        }
+        matched_cells
+    }
+
+    /// Returns the index of the match after `current`, out of `count` matches. With no current
+    /// match the first match is next. From the last match this wraps around to the first if `wrap`
+    /// is set, otherwise there is no next match.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::next_match;
+    ///
+    /// assert_eq!(next_match(3, None, false), Some(0));
+    /// assert_eq!(next_match(3, Some(1), false), Some(2));
+    /// assert_eq!(next_match(3, Some(2), false), None);
+    /// assert_eq!(next_match(3, Some(2), true), Some(0));
+    /// assert_eq!(next_match(0, None, true), None);
+    /// ```
+    pub fn next_match(count: usize, current: Option<usize>, wrap: bool) -> Option<usize> {
+        match current {
+            _ if count == 0 => None,
+            None => Some(0),
+            Some(i) if i + 1 < count => Some(i + 1),
+            Some(_) if wrap => Some(0),
+            Some(_) => None,
+        }
+    }
+
+    /// Returns the index of the match before `current`, out of `count` matches. With no current
+    /// match the last match is previous. From the first match this wraps around to the last if
+    /// `wrap` is set, otherwise there is no previous match.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::previous_match;
+    ///
+    /// assert_eq!(previous_match(3, None, false), Some(2));
+    /// assert_eq!(previous_match(3, Some(1), false), Some(0));
+    /// assert_eq!(previous_match(3, Some(0), false), None);
+    /// assert_eq!(previous_match(3, Some(0), true), Some(2));
+    /// assert_eq!(previous_match(3, Some(7), false), Some(2));
+    /// ```
+    pub fn previous_match(count: usize, current: Option<usize>, wrap: bool) -> Option<usize> {
+        match current {
+            _ if count == 0 => None,
+            None => Some(count - 1),
+            Some(i) if i >= count => Some(count - 1),
+            Some(0) if wrap => Some(count - 1),
+            Some(0) => None,
+            Some(i) => Some(i - 1),
+        }
+    }
+
+    /// Returns the text of a match within `cell` with up to `context` characters either side of
+    /// it, marking where the cell was cut short with an ellipsis. Line breaks are shown as spaces,
+    /// so that the snippet fits on one line.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::get_snippet;
+    ///
+    /// let cell = "the quick brown fox jumps";
+    /// assert_eq!(get_snippet(cell, &(10..15), 4), "…ick brown fox…");
+    /// assert_eq!(get_snippet(cell, &(0..3), 4), "the qui…");
+    /// assert_eq!(get_snippet("two\nlines", &(0..3), 10), "two lines");
+    /// ```
+    pub fn get_snippet(cell: &str, range: &Range<usize>, context: usize) -> String {
+        let before: Vec<char> = cell[..range.start]
+            .chars()
+            .rev()
+            .take(context + 1)
+            .collect();
+        let after: Vec<char> = cell[range.end..].chars().take(context + 1).collect();
+
+        let mut snippet = String::new();
+        if before.len() > context {
+            snippet.push('…');
+        }
+        snippet.extend(before.iter().take(context).rev());
+        snippet.push_str(&cell[range.clone()]);
+        snippet.extend(after.iter().take(context));
+        if after.len() > context {
+            snippet.push('…');
+        }
+        snippet.replace(['\r', '\n'], " ")
+    }
+
+    /// Returns the element of `vec` at index `i`, the row of the `i`th match, or `None` if there
+    /// is no such match.
     ///
     /// # Example
     ///```
@@ -58,15 +508,11 @@ pub mod find {
     ///
     /// let vec = vec![1, 2, 3, 4, 5];
     ///
-    /// assert_eq!(find_row_of_next(vec, 0), 1);
-    /// assert_eq!(find_row_of_next(vec, 3), 4);
-    /// assert_eq!(find_row_of_next(vec, 4), 0);
+    /// assert_eq!(find_row_of_next(&vec, 0), Some(1));
+    /// assert_eq!(find_row_of_next(&vec, 3), Some(4));
+    /// assert_eq!(find_row_of_next(&vec, 5), None);
     /// ```
-    pub fn find_row_of_next(vec: Vec<usize>, i: usize) -> usize {
-        let mut match_index: usize = 0;
-        if i < vec.len() {
-            match_index = vec[i];
-        }
-        match_index
+    pub fn find_row_of_next(vec: &[usize], i: usize) -> Option<usize> {
+        vec.get(i).copied()
     }
 }
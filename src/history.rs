@@ -0,0 +1,357 @@
+pub mod history {
+    use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+    use std::error::Error;
+
+    use crate::edit::edit::EditOverlay;
+    use crate::reader::reader::get_column_name;
+
+    /// Identifies a recipe file, stored in the first field of its first line.
+    const RECIPE_MAGIC: &str = "csv_viewer_recipe";
+    const RECIPE_VERSION: &str = "1";
+
+    /// A change made to the data of the open file. Rows and columns are numbered by their position
+    /// in the edited file at the time of the change, starting at 0.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Edit {
+        SetCell {
+            row: usize,
+            column: usize,
+            value: String,
+        },
+        /// Cells set at once, e.g. by pasting, as the row, column and value of each cell.
+        SetCells {
+            cells: Vec<(usize, usize, String)>,
+        },
+        InsertRow {
+            row: usize,
+        },
+        DeleteRow {
+            row: usize,
+        },
+        InsertColumn {
+            column: usize,
+            name: String,
+        },
+        DeleteColumn {
+            column: usize,
+        },
+        RenameColumn {
+            column: usize,
+            name: String,
+        },
+    }
+
+    impl Edit {
+        /// Makes the change to `edits`. Returns false if it refers to a row or column that is not
+        /// there, in which case nothing is changed.
+        pub fn apply(&self, edits: &mut EditOverlay) -> bool {
+            match self {
+                Edit::SetCell { row, column, value } => {
+                    edits.set_cell(*row, *column, value.clone())
+                }
+                Edit::SetCells { cells } => {
+                    let mut changed = false;
+                    for (row, column, value) in cells {
+                        changed |= edits.set_cell(*row, *column, value.clone());
+                    }
+                    changed
+                }
+                Edit::InsertRow { row } => {
+                    edits.insert_row(*row);
+                    true
+                }
+                Edit::DeleteRow { row } => edits.delete_row(*row),
+                Edit::InsertColumn { column, name } => {
+                    edits.insert_column(*column, name);
+                    true
+                }
+                Edit::DeleteColumn { column } => edits.delete_column(*column),
+                Edit::RenameColumn { column, name } => edits.rename_column(*column, name),
+            }
+        }
+
+        /// Describes the change, naming cells like a spreadsheet, e.g. "Edit cell B12".
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::history::history::Edit;
+        ///
+        /// let edit = Edit::SetCell { row: 11, column: 1, value: String::from("x") };
+        /// assert_eq!(edit.describe(), "Edit cell B12");
+        /// let edit = Edit::RenameColumn { column: 27, name: String::from("total") };
+        /// assert_eq!(edit.describe(), "Rename column AB to \"total\"");
+        /// ```
+        pub fn describe(&self) -> String {
+            match self {
+                Edit::SetCell { row, column, .. } => {
+                    format!("Edit cell {}{}", get_column_name(*column), row + 1)
+                }
+                Edit::SetCells { cells } => format!("Paste {} cells", cells.len()),
+                Edit::InsertRow { row } => format!("Insert row {}", row + 1),
+                Edit::DeleteRow { row } => format!("Delete row {}", row + 1),
+                Edit::InsertColumn { column, .. } => {
+                    format!("Insert column {}", get_column_name(*column))
+                }
+                Edit::DeleteColumn { column } => {
+                    format!("Delete column {}", get_column_name(*column))
+                }
+                Edit::RenameColumn { column, name } => {
+                    format!("Rename column {} to \"{}\"", get_column_name(*column), name)
+                }
+            }
+        }
+
+        /// Returns the fields of the line of a recipe file storing the change.
+        fn to_fields(&self) -> Vec<String> {
+            match self {
+                Edit::SetCell { row, column, value } => vec![
+                    String::from("set_cell"),
+                    row.to_string(),
+                    column.to_string(),
+                    value.clone(),
+                ],
+                Edit::SetCells { cells } => {
+                    let mut fields = vec![String::from("set_cells")];
+                    for (row, column, value) in cells {
+                        fields.extend([row.to_string(), column.to_string(), value.clone()]);
+                    }
+                    fields
+                }
+                Edit::InsertRow { row } => vec![String::from("insert_row"), row.to_string()],
+                Edit::DeleteRow { row } => vec![String::from("delete_row"), row.to_string()],
+                Edit::InsertColumn { column, name } => vec![
+                    String::from("insert_column"),
+                    column.to_string(),
+                    name.clone(),
+                ],
+                Edit::DeleteColumn { column } => {
+                    vec![String::from("delete_column"), column.to_string()]
+                }
+                Edit::RenameColumn { column, name } => vec![
+                    String::from("rename_column"),
+                    column.to_string(),
+                    name.clone(),
+                ],
+            }
+        }
+
+        /// Reads a change from a line of a recipe file.
+        fn from_record(record: &StringRecord) -> Option<Edit> {
+            let number = |i: usize| -> Option<usize> { record.get(i)?.parse().ok() };
+            let text = |i: usize| -> Option<String> { record.get(i).map(String::from) };
+            match record.get(0)? {
+                "set_cell" => Some(Edit::SetCell {
+                    row: number(1)?,
+                    column: number(2)?,
+                    value: text(3)?,
+                }),
+                "set_cells" => {
+                    let mut cells = Vec::new();
+                    let mut i = 1;
+                    while i < record.len() {
+                        cells.push((number(i)?, number(i + 1)?, text(i + 2)?));
+                        i += 3;
+                    }
+                    Some(Edit::SetCells { cells })
+                }
+                "insert_row" => Some(Edit::InsertRow { row: number(1)? }),
+                "delete_row" => Some(Edit::DeleteRow { row: number(1)? }),
+                "insert_column" => Some(Edit::InsertColumn {
+                    column: number(1)?,
+                    name: text(2)?,
+                }),
+                "delete_column" => Some(Edit::DeleteColumn { column: number(1)? }),
+                "rename_column" => Some(Edit::RenameColumn {
+                    column: number(1)?,
+                    name: text(2)?,
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    /// The changes made to the open file in order, so they can be undone and redone.
+    ///
+    /// Undoing a change rebuilds the edits from those of the file when it was opened by making
+    /// again every change before it. Making a new change after undoing drops the changes undone.
+    ///
+    /// # Example
+    /// ```
+    /// use csv::StringRecord;
+    /// use csv_viewer::edit::edit::EditOverlay;
+    /// use csv_viewer::history::history::{Edit, EditHistory};
+    ///
+    /// let mut edits = EditOverlay::new(&StringRecord::from(vec!["name", "rank"]), 3);
+    /// let mut history = EditHistory::new(&edits);
+    /// history.apply(Edit::InsertRow { row: 0 }, &mut edits);
+    /// history.apply(Edit::DeleteColumn { column: 1 }, &mut edits);
+    /// assert_eq!((edits.len(), edits.headers().len()), (4, 1));
+    ///
+    /// assert!(history.undo(&mut edits));
+    /// assert_eq!((edits.len(), edits.headers().len()), (4, 2));
+    /// assert!(history.undo(&mut edits));
+    /// assert!(!history.undo(&mut edits));
+    /// assert!(!edits.is_modified());
+    ///
+    /// assert!(history.redo(&mut edits));
+    /// assert_eq!(edits.len(), 4);
+    /// // a new change drops the column deletion left to redo
+    /// history.apply(Edit::DeleteRow { row: 0 }, &mut edits);
+    /// assert!(!history.redo(&mut edits));
+    /// assert_eq!(history.edits().len(), 2);
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct EditHistory {
+        // the edits of the file when it was opened
+        base: EditOverlay,
+        edits: Vec<Edit>,
+        // number of the edits in effect, the rest have been undone
+        applied: usize,
+    }
+
+    impl EditHistory {
+        /// Starts an empty history of changes to `edits`.
+        pub fn new(edits: &EditOverlay) -> EditHistory {
+            EditHistory {
+                base: edits.clone(),
+                edits: Vec::new(),
+                applied: 0,
+            }
+        }
+
+        /// Makes `edit` to `edits` and adds it to the history, dropping any changes undone.
+        /// Returns false, and leaves the history as it is, if the change could not be made.
+        pub fn apply(&mut self, edit: Edit, edits: &mut EditOverlay) -> bool {
+            if !edit.apply(edits) {
+                return false;
+            }
+            self.edits.truncate(self.applied);
+            self.edits.push(edit);
+            self.applied += 1;
+            true
+        }
+
+        /// Undoes the last change in effect. Returns false if there is none.
+        pub fn undo(&mut self, edits: &mut EditOverlay) -> bool {
+            if self.applied == 0 {
+                return false;
+            }
+            self.go_to(self.applied - 1, edits);
+            true
+        }
+
+        /// Makes again the last change undone. Returns false if there is none.
+        pub fn redo(&mut self, edits: &mut EditOverlay) -> bool {
+            match self.edits.get(self.applied) {
+                Some(edit) => {
+                    edit.apply(edits);
+                    self.applied += 1;
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Undoes or redoes changes until the first `applied` changes of the history are in effect.
+        pub fn go_to(&mut self, applied: usize, edits: &mut EditOverlay) {
+            let applied = applied.min(self.edits.len());
+            if applied < self.applied {
+                *edits = self.base.clone();
+                for edit in self.edits[..applied].iter() {
+                    edit.apply(edits);
+                }
+            } else {
+                for edit in self.edits[self.applied..applied].iter() {
+                    edit.apply(edits);
+                }
+            }
+            self.applied = applied;
+        }
+
+        /// Returns every change in the history, including those undone.
+        pub fn edits(&self) -> &[Edit] {
+            &self.edits
+        }
+
+        /// Returns the number of changes in effect, the first of the history.
+        pub fn applied(&self) -> usize {
+            self.applied
+        }
+
+        /// Returns true if there is a change to undo.
+        pub fn can_undo(&self) -> bool {
+            self.applied > 0
+        }
+
+        /// Returns true if there is a change to redo.
+        pub fn can_redo(&self) -> bool {
+            self.applied < self.edits.len()
+        }
+
+        /// Writes the changes in effect to a recipe file at `recipe_path`, so the same changes
+        /// can be made to another file.
+        ///
+        /// # Example
+        /// ```
+        /// use csv::StringRecord;
+        /// use csv_viewer::edit::edit::EditOverlay;
+        /// use csv_viewer::history::history::{load_recipe, Edit, EditHistory};
+        ///
+        /// let mut edits = EditOverlay::new(&StringRecord::from(vec!["name", "rank"]), 3);
+        /// let mut history = EditHistory::new(&edits);
+        /// history.apply(Edit::RenameColumn { column: 1, name: String::from("place") }, &mut edits);
+        /// let set_cell = Edit::SetCell { row: 2, column: 0, value: String::from("a, \"b\"") };
+        /// history.apply(set_cell.clone(), &mut edits);
+        /// let pasted = Edit::SetCells {
+        ///     cells: vec![(0, 0, String::from("x")), (1, 0, String::from("y\nz"))],
+        /// };
+        /// history.apply(pasted.clone(), &mut edits);
+        ///
+        /// let path = std::env::temp_dir().join("csv_viewer_recipe.csv").display().to_string();
+        /// history.save_recipe(&path).unwrap();
+        /// let recipe = load_recipe(&path).unwrap();
+        /// assert_eq!(recipe.len(), 3);
+        /// assert_eq!(recipe[1], set_cell);
+        /// assert_eq!(recipe[2], pasted);
+        /// ```
+        pub fn save_recipe(&self, recipe_path: &str) -> Result<(), Box<dyn Error>> {
+            let mut writer = WriterBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(recipe_path)?;
+            writer.write_record([RECIPE_MAGIC, RECIPE_VERSION])?;
+            for edit in self.edits[..self.applied].iter() {
+                writer.write_record(edit.to_fields())?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+    }
+
+    /// Reads the changes stored in the recipe file at `recipe_path`, in the order they were made.
+    pub fn load_recipe(recipe_path: &str) -> Result<Vec<Edit>, Box<dyn Error>> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(recipe_path)?;
+        let mut records = reader.records();
+
+        let header = match records.next() {
+            Some(header) => header?,
+            None => return Err(From::from("Recipe file is empty")),
+        };
+        if header.get(0) != Some(RECIPE_MAGIC) || header.get(1) != Some(RECIPE_VERSION) {
+            return Err(From::from("Not a recipe file"));
+        }
+        let mut edits = Vec::new();
+        for (line, result) in records.enumerate() {
+            let record = result?;
+            match Edit::from_record(&record) {
+                Some(edit) => edits.push(edit),
+                // the header is the first line of the file
+                None => return Err(From::from(format!("Unknown change on line {}", line + 2))),
+            }
+        }
+        Ok(edits)
+    }
+}
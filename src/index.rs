@@ -0,0 +1,298 @@
+pub mod index {
+    use csv::{Position, ReaderBuilder, StringRecord, WriterBuilder};
+    use std::error::Error;
+    use std::fs;
+    use std::fs::File;
+    use std::time::UNIX_EPOCH;
+
+    use crate::reader::reader::{Dialect, LineEnding};
+
+    /// Number of records between each stored byte offset when building a new index.
+    pub const DEFAULT_INDEX_STEP: usize = 64;
+
+    /// Identifies an index sidecar file, stored in the first field of its first line.
+    const INDEX_MAGIC: &str = "csv_viewer_index";
+    const INDEX_VERSION: &str = "3";
+
+    /// Byte offsets of every `step`th record within a delimited file, so that a page deep inside
+    /// the file can be read by seeking straight to it rather than parsing from the top.
+    ///
+    /// Record numbers used by the index count every record in the file, including the header row.
+    #[derive(Debug, Clone)]
+    pub struct RowIndex {
+        step: usize,
+        positions: Vec<Position>,
+        total_records: usize,
+        // the dialect the file was parsed with, as the positions depend on every setting of it
+        dialect: Dialect,
+        source_len: u64,
+        source_modified: u128,
+    }
+
+    impl RowIndex {
+        /// Parses the whole file at `file_path`, storing the position of every `step`th record.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::index::index::RowIndex;
+        /// use csv_viewer::reader::reader::Dialect;
+        ///
+        /// let index = RowIndex::build("tests/test_uspop.csv", &Dialect::default(), 16).unwrap();
+        /// // 201 data rows plus the header row
+        /// assert_eq!(index.total_records(), 202);
+        /// ```
+        pub fn build(
+            file_path: &str,
+            dialect: &Dialect,
+            step: usize,
+        ) -> Result<RowIndex, Box<dyn Error>> {
+            let step = step.max(1);
+            let (source_len, source_modified) = get_file_stamp(file_path)?;
+            let mut reader = dialect
+                .reader_builder()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(file_path)?;
+
+            let mut positions: Vec<Position> = Vec::new();
+            let mut record = StringRecord::new();
+            let mut total_records = 0;
+            loop {
+                let pos = reader.position().clone();
+                match reader.read_record(&mut record) {
+                    Ok(false) => break,
+                    Ok(true) => {}
+                    Err(e) => println!(
+                        "Error: record {} (line {}): {}",
+                        total_records,
+                        pos.line(),
+                        e
+                    ),
+                }
+                if total_records % step == 0 {
+                    positions.push(pos);
+                }
+                total_records += 1;
+            }
+
+            Ok(RowIndex {
+                step,
+                positions,
+                total_records,
+                dialect: dialect.clone(),
+                source_len,
+                source_modified,
+            })
+        }
+
+        /// Loads the index stored in the sidecar file of `file_path`.
+        /// Returns `None` if there is no sidecar, or if the source file has changed size or
+        /// modification time since it was written, or was indexed with a different dialect.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::index::index::RowIndex;
+        /// use csv_viewer::reader::reader::Dialect;
+        ///
+        /// let dialect = Dialect::default();
+        /// RowIndex::load_or_build("tests/test_quoted_newlines.csv", &dialect, 2).unwrap();
+        /// assert!(RowIndex::load("tests/test_quoted_newlines.csv", &dialect).is_some());
+        /// let escaped = Dialect { escape: Some(b'\\'), double_quote: false, ..Dialect::default() };
+        /// assert!(RowIndex::load("tests/test_quoted_newlines.csv", &escaped).is_none());
+        /// ```
+        pub fn load(file_path: &str, dialect: &Dialect) -> Option<RowIndex> {
+            let (source_len, source_modified) = get_file_stamp(file_path).ok()?;
+            let mut reader = ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(get_sidecar_path(file_path))
+                .ok()?;
+            let mut records = reader.records();
+
+            let header = records.next()?.ok()?;
+            if header.get(0)? != INDEX_MAGIC || header.get(1)? != INDEX_VERSION {
+                return None;
+            }
+            let stored_len: u64 = header.get(2)?.parse().ok()?;
+            let stored_modified: u128 = header.get(3)?.parse().ok()?;
+            let escape = match header.get(6)? {
+                "" => None,
+                escape => Some(escape.parse().ok()?),
+            };
+            let stored_dialect = Dialect {
+                delimiter: header.get(4)?.parse().ok()?,
+                quote: header.get(5)?.parse().ok()?,
+                escape,
+                double_quote: header.get(7)? == "1",
+                terminator: match header.get(8)? {
+                    "crlf" => LineEnding::Crlf,
+                    "cr" => LineEnding::Cr,
+                    _ => LineEnding::Lf,
+                },
+            };
+            if stored_len != source_len
+                || stored_modified != source_modified
+                || stored_dialect != *dialect
+            {
+                return None;
+            }
+            let step: usize = header.get(9)?.parse().ok()?;
+            let total_records: usize = header.get(10)?.parse().ok()?;
+
+            let mut positions: Vec<Position> = Vec::new();
+            for result in records {
+                let record = result.ok()?;
+                let mut pos = Position::new();
+                pos.set_byte(record.get(0)?.parse().ok()?)
+                    .set_line(record.get(1)?.parse().ok()?)
+                    .set_record(record.get(2)?.parse().ok()?);
+                positions.push(pos);
+            }
+
+            Some(RowIndex {
+                step: step.max(1),
+                positions,
+                total_records,
+                dialect: stored_dialect,
+                source_len,
+                source_modified,
+            })
+        }
+
+        /// Writes the index to the sidecar file of `file_path`, so later opens can skip the build.
+        pub fn save(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+            let mut writer = WriterBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(get_sidecar_path(file_path))?;
+            writer.write_record(&[
+                INDEX_MAGIC.to_string(),
+                INDEX_VERSION.to_string(),
+                self.source_len.to_string(),
+                self.source_modified.to_string(),
+                self.dialect.delimiter.to_string(),
+                self.dialect.quote.to_string(),
+                self.dialect
+                    .escape
+                    .map_or(String::new(), |escape| escape.to_string()),
+                String::from(if self.dialect.double_quote { "1" } else { "0" }),
+                String::from(match self.dialect.terminator {
+                    LineEnding::Lf => "lf",
+                    LineEnding::Crlf => "crlf",
+                    LineEnding::Cr => "cr",
+                }),
+                self.step.to_string(),
+                self.total_records.to_string(),
+            ])?;
+            for pos in self.positions.iter() {
+                writer.write_record(&[
+                    pos.byte().to_string(),
+                    pos.line().to_string(),
+                    pos.record().to_string(),
+                ])?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+
+        /// Loads the cached index for `file_path` if it is still valid, otherwise builds a new one
+        /// and tries to cache it. Failing to write the sidecar (e.g. a read-only directory) is not
+        /// an error, the index is just rebuilt next time.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::index::index::RowIndex;
+        /// use csv_viewer::reader::reader::Dialect;
+        ///
+        /// let dialect = Dialect::default();
+        /// let built = RowIndex::load_or_build("tests/test_uspop.csv", &dialect, 16).unwrap();
+        /// let cached = RowIndex::load("tests/test_uspop.csv", &dialect).unwrap();
+        /// assert_eq!(built.total_records(), cached.total_records());
+        /// assert_eq!(built.locate(150), cached.locate(150));
+        /// ```
+        pub fn load_or_build(
+            file_path: &str,
+            dialect: &Dialect,
+            step: usize,
+        ) -> Result<RowIndex, Box<dyn Error>> {
+            if let Some(index) = RowIndex::load(file_path, dialect) {
+                return Ok(index);
+            }
+            let index = RowIndex::build(file_path, dialect, step)?;
+            match index.save(file_path) {
+                Ok(()) => {}
+                Err(e) => println!("Cannot write index for {}: {}", file_path, e),
+            }
+            Ok(index)
+        }
+
+        /// Returns the number of records in the file, including the header row if there is one.
+        pub fn total_records(&self) -> usize {
+            self.total_records
+        }
+
+        /// Returns the nearest stored position at or before `record`, together with the number of
+        /// records that must be read past it to arrive at `record`.
+        /// Returns `None` if `record` is past the end of the file.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::index::index::RowIndex;
+        /// use csv_viewer::reader::reader::Dialect;
+        ///
+        /// let index = RowIndex::build("tests/test_uspop.csv", &Dialect::default(), 16).unwrap();
+        /// let (pos, skip) = index.locate(35).unwrap();
+        /// assert_eq!(pos.record(), 32);
+        /// assert_eq!(skip, 3);
+        /// assert!(index.locate(202).is_none());
+        /// ```
+        pub fn locate(&self, record: usize) -> Option<(Position, usize)> {
+            if record >= self.total_records {
+                return None;
+            }
+            let pos = self.positions.get(record / self.step)?;
+            Some((pos.clone(), record % self.step))
+        }
+    }
+
+    /// Returns the path of the sidecar file used to cache the index of `file_path`.
+    pub fn get_sidecar_path(file_path: &str) -> String {
+        format!("{}.idx", file_path)
+    }
+
+    /// Returns the size in bytes and the modification time in nanoseconds of the file at `file_path`.
+    fn get_file_stamp(file_path: &str) -> Result<(u64, u128), Box<dyn Error>> {
+        let metadata = fs::metadata(file_path)?;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos();
+        Ok((metadata.len(), modified))
+    }
+
+    /// Opens `file_path` for reading with a reader positioned at the start of `record`,
+    /// using the index to seek most of the way there.
+    pub fn get_reader_at_record(
+        file_path: &str,
+        index: &RowIndex,
+        record: usize,
+        dialect: &Dialect,
+    ) -> Result<Option<csv::Reader<File>>, Box<dyn Error>> {
+        let (pos, skip) = match index.locate(record) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(file_path)?;
+        reader.seek(pos)?;
+
+        // a record that cannot be read is still a record, so it is skipped like the others
+        let mut skipped = StringRecord::new();
+        for _ in 0..skip {
+            if let Ok(false) = reader.read_record(&mut skipped) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(reader))
+    }
+}
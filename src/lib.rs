@@ -1,4 +1,16 @@
+pub mod cache;
+pub mod cli;
+pub mod clipboard;
+pub mod commands;
+pub mod compare;
+pub mod edit;
+pub mod export;
+pub mod filter;
 pub mod find;
+pub mod history;
+pub mod index;
 pub mod reader;
+pub mod replace;
+pub mod sniff;
 pub mod sort;
 pub mod viewer_app;
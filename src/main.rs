@@ -1,10 +1,65 @@
+pub mod cache;
+pub mod cli;
+pub mod clipboard;
+pub mod commands;
+pub mod compare;
+pub mod edit;
+pub mod export;
+pub mod filter;
 pub mod find;
+pub mod history;
+pub mod index;
 pub mod reader;
+pub mod replace;
+pub mod sniff;
 pub mod sort;
 pub mod viewer_app;
 
+use std::path::Path;
+use std::process;
+
+use cli::cli::{parse_args, USAGE};
+use commands::commands::{
+    parse_command, run, CommandName, COMMANDS_USAGE, EXIT_FAILURE, EXIT_USAGE,
+};
 use viewer_app::viewer_app::run_app;
 
 fn main() {
-    run_app().expect("Runtime Error");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // a command as the first argument runs it without opening a window
+    if let Some(name) = args.first().and_then(|arg| CommandName::from_name(arg)) {
+        let command = match parse_command(name, args.into_iter().skip(1)) {
+            Ok(command) => command,
+            Err(e) => {
+                eprintln!("Error: {}\n\n{}", e, COMMANDS_USAGE);
+                process::exit(EXIT_USAGE);
+            }
+        };
+        match run(&command) {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(EXIT_FAILURE);
+            }
+        }
+    }
+
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if args.help {
+        println!("{}\n\n{}", USAGE, COMMANDS_USAGE);
+        return;
+    }
+    if let Some(path) = args.file_path.as_deref().filter(|path| *path != "-") {
+        if !Path::new(path).is_file() {
+            eprintln!("Error: No such file: {}", path);
+            process::exit(EXIT_USAGE);
+        }
+    }
+    run_app(args).expect("Runtime Error");
 }
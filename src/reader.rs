@@ -1,12 +1,101 @@
 pub mod reader {
-    use csv::{Reader, ReaderBuilder, StringRecord};
-    use std::borrow::{Borrow};
+    use csv::{ByteRecord, Reader, ReaderBuilder, StringRecord, Terminator, WriterBuilder};
+    use std::borrow::Borrow;
+    use std::error::Error;
     use std::fs;
-    use std::fs::{File};
+    use std::fs::File;
     use std::io;
-    use std::io::{BufRead, BufReader, Read, Stdin};
+    use std::io::{BufReader, Read, Seek, SeekFrom, Stdin};
     use std::mem::size_of_val;
 
+    use crate::index::index::{get_reader_at_record, RowIndex};
+
+    /// The line ending that ends each record of a delimited file.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum LineEnding {
+        Lf,
+        Crlf,
+        Cr,
+    }
+
+    /// The format details needed to parse a delimited file.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Dialect {
+        pub delimiter: u8,
+        pub quote: u8,
+        /// Escape character used inside quoted fields, when quotes are not escaped by doubling.
+        pub escape: Option<u8>,
+        /// Whether a quote inside a quoted field is written as two quotes (`""`).
+        pub double_quote: bool,
+        /// Line ending written after each record. Any line ending is accepted when reading.
+        pub terminator: LineEnding,
+    }
+
+    impl Default for Dialect {
+        fn default() -> Self {
+            Self {
+                delimiter: b',',
+                quote: b'"',
+                escape: None,
+                double_quote: true,
+                terminator: LineEnding::Lf,
+            }
+        }
+    }
+
+    impl Dialect {
+        /// Returns the default dialect with its delimiter replaced by `delimiter`.
+        pub fn with_delimiter(delimiter: char) -> Dialect {
+            Dialect {
+                delimiter: u8::try_from(delimiter).unwrap_or(b','),
+                ..Default::default()
+            }
+        }
+
+        /// Returns a csv `ReaderBuilder` configured to parse this dialect.
+        pub fn reader_builder(&self) -> ReaderBuilder {
+            let mut builder = ReaderBuilder::new();
+            builder
+                .delimiter(self.delimiter)
+                .quote(self.quote)
+                .escape(self.escape)
+                .double_quote(self.double_quote);
+            builder
+        }
+
+        /// Returns a csv `WriterBuilder` configured to write this dialect.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::reader::reader::{Dialect, LineEnding};
+        ///
+        /// let dialect = Dialect {
+        ///     terminator: LineEnding::Crlf,
+        ///     ..Dialect::with_delimiter(';')
+        /// };
+        /// let mut writer = dialect.writer_builder().from_writer(vec![]);
+        /// writer.write_record(&["a;b", "c"]).unwrap();
+        /// let written = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        /// assert_eq!(written, "\"a;b\";c\r\n");
+        /// ```
+        pub fn writer_builder(&self) -> WriterBuilder {
+            let mut builder = WriterBuilder::new();
+            builder
+                .delimiter(self.delimiter)
+                .quote(self.quote)
+                .double_quote(self.double_quote)
+                .terminator(match self.terminator {
+                    LineEnding::Lf => Terminator::Any(b'\n'),
+                    LineEnding::Crlf => Terminator::CRLF,
+                    LineEnding::Cr => Terminator::Any(b'\r'),
+                });
+            if let Some(escape) = self.escape {
+                builder.escape(escape);
+            }
+            builder
+        }
+    }
+
     /// Returns a reader object from stdin input
     pub fn get_reader_stdin() -> Reader<Stdin> {
         let mut reader = Reader::from_reader(io::stdin());
@@ -20,17 +109,60 @@ pub mod reader {
         reader.unwrap()
     }
 
-    /// Extracts and returns the headers from a file-read reader object
-    pub fn get_headers_from_file(file_path: String, delimiter: char) -> StringRecord {
-        let file = File::open(file_path).unwrap();
+    /// Extracts and returns the headers from a file-read reader object.
+    /// If the file has no header row, the columns are named A, B, C... like a spreadsheet, with
+    /// the number of columns taken from the first record.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::reader::reader::{get_headers_from_file, Dialect};
+    ///
+    /// let file_path = String::from("tests/test_find_matching.csv");
+    /// let headers = get_headers_from_file(file_path, &Dialect::default(), false).unwrap();
+    /// assert_eq!(headers, vec!["A", "B"]);
+    /// ```
+    pub fn get_headers_from_file(
+        file_path: String,
+        dialect: &Dialect,
+        has_headers: bool,
+    ) -> Result<StringRecord, Box<dyn Error>> {
+        let file = File::open(file_path)?;
         let mut reader = BufReader::new(file);
-        let mut header_reader = ReaderBuilder::new()
-            .delimiter(u8::try_from(delimiter).unwrap())
+        let mut header_reader = dialect
+            .reader_builder()
             .has_headers(false)
+            .flexible(true)
             .from_reader(&mut reader);
-        let header = header_reader.headers().unwrap().clone();
+        let header = header_reader.headers()?.clone();
+
+        if !has_headers {
+            return Ok((0..header.len()).map(get_column_name).collect());
+        }
+        Ok(header)
+    }
 
-        header
+    /// Returns the spreadsheet-style name of the column at `index`: A to Z, then AA, AB and so on.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::reader::reader::get_column_name;
+    ///
+    /// assert_eq!(get_column_name(0), "A");
+    /// assert_eq!(get_column_name(25), "Z");
+    /// assert_eq!(get_column_name(26), "AA");
+    /// assert_eq!(get_column_name(701), "ZZ");
+    /// assert_eq!(get_column_name(702), "AAA");
+    /// ```
+    pub fn get_column_name(index: usize) -> String {
+        let mut name = Vec::new();
+        let mut n = index + 1;
+        while n > 0 {
+            n -= 1;
+            name.push(b'A' + (n % 26) as u8);
+            n /= 26;
+        }
+        name.reverse();
+        String::from_utf8(name).unwrap()
     }
 
     /// Extracts and returns the records from a file-read reader object
@@ -79,19 +211,159 @@ pub mod reader {
         records
     }
 
-    /// Returns the row count of the file.
-    pub fn get_row_count(file_path: Option<String>) -> usize {
+    /// Returns the number of records in the file, including the header row if there is one.
+    /// Records are counted by the csv parser, so quoted fields spanning several physical lines
+    /// count as a single record. Records that fail to parse are still counted, and reported along
+    /// with the physical line they start on.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::reader::reader::{get_row_count, Dialect};
+    ///
+    /// // 9 physical lines, but only 5 records
+    /// let file_path = Option::from(String::from("tests/test_quoted_newlines.csv"));
+    /// assert_eq!(get_row_count(file_path, &Dialect::default()), 5);
+    /// ```
+    pub fn get_row_count(file_path: Option<String>, dialect: &Dialect) -> usize {
         let file = File::open(file_path.unwrap()).unwrap();
-        let reader = BufReader::new(file);
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(BufReader::new(file));
 
         let mut number_of_rows = 0;
-        for _ in reader.lines() {
+        let mut record = ByteRecord::new();
+        loop {
+            let line = reader.position().line();
+            match reader.read_byte_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => {}
+                Err(e) => println!(
+                    "Error: Cannot Read Record {} (line {}): {}",
+                    number_of_rows, line, e
+                ),
+            }
             number_of_rows += 1;
         }
 
         number_of_rows
     }
 
+    /// Prints a record that failed to parse, along with the physical line it starts on when known.
+    fn print_record_error(e: &csv::Error) {
+        match e.position() {
+            Some(pos) => println!("Error: Cannot Read Record (line {}): {}", pos.line(), e),
+            None => println!("Error: Cannot Read Record: {}", e),
+        }
+    }
+
+    /// Returns the physical line number (starting at 1) that the record at index `record` begins on,
+    /// counting the header row as record 0. Returns `None` if the file has fewer records.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::reader::reader::{get_record_line, Dialect};
+    ///
+    /// let dialect = Dialect::default();
+    ///
+    /// let file_path = Option::from(String::from("tests/test_quoted_newlines.csv"));
+    /// assert_eq!(get_record_line(file_path.clone(), &dialect, 0), Some(1));
+    /// assert_eq!(get_record_line(file_path.clone(), &dialect, 2), Some(4));
+    /// assert_eq!(get_record_line(file_path.clone(), &dialect, 4), Some(6));
+    /// assert_eq!(get_record_line(file_path, &dialect, 5), None);
+    /// ```
+    pub fn get_record_line(
+        file_path: Option<String>,
+        dialect: &Dialect,
+        record: usize,
+    ) -> Option<u64> {
+        let file = File::open(file_path.unwrap()).ok()?;
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(BufReader::new(file));
+
+        let mut skipped = ByteRecord::new();
+        for _ in 0..record {
+            // a record that fails to parse still occupies its lines
+            if let Ok(false) = reader.read_byte_record(&mut skipped) {
+                return None;
+            }
+        }
+        let line = reader.position().line();
+        match reader.read_byte_record(&mut skipped) {
+            Ok(false) => None,
+            _ => Some(line),
+        }
+    }
+
+    /// Returns the index of the record that physical line `line` (starting at 1) is part of,
+    /// counting the header row as record 0. A line inside a quoted field belongs to the record the
+    /// field is in. Returns `None` if the file has fewer lines.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::reader::reader::{get_record_at_line, Dialect};
+    ///
+    /// let dialect = Dialect::default();
+    ///
+    /// let file_path = Option::from(String::from("tests/test_quoted_newlines.csv"));
+    /// assert_eq!(get_record_at_line(file_path.clone(), &dialect, 1), Some(0));
+    /// assert_eq!(get_record_at_line(file_path.clone(), &dialect, 3), Some(1));
+    /// assert_eq!(get_record_at_line(file_path.clone(), &dialect, 4), Some(2));
+    /// assert_eq!(get_record_at_line(file_path.clone(), &dialect, 8), Some(4));
+    /// assert_eq!(get_record_at_line(file_path.clone(), &dialect, 9), None);
+    /// assert_eq!(get_record_at_line(file_path, &dialect, 0), None);
+    ///
+    /// // the last line has no line ending
+    /// let file_path = Option::from(String::from("tests/test_uspop.csv"));
+    /// assert_eq!(get_record_at_line(file_path.clone(), &dialect, 202), Some(201));
+    /// assert_eq!(get_record_at_line(file_path, &dialect, 203), None);
+    /// ```
+    pub fn get_record_at_line(
+        file_path: Option<String>,
+        dialect: &Dialect,
+        line: u64,
+    ) -> Option<usize> {
+        if line == 0 {
+            return None;
+        }
+        let mut file = File::open(file_path.unwrap()).ok()?;
+        let mut last_byte = [0u8];
+        let ends_with_newline = file.seek(SeekFrom::End(-1)).is_ok()
+            && file.read_exact(&mut last_byte).is_ok()
+            && last_byte[0] == b'\n';
+        file.seek(SeekFrom::Start(0)).ok()?;
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(BufReader::new(file));
+
+        let mut found = None;
+        let mut skipped = ByteRecord::new();
+        loop {
+            if reader.position().line() > line {
+                // the last record read started on or before the line, and the next one after it
+                return found;
+            }
+            // a record that fails to parse still occupies its lines
+            if let Ok(false) = reader.read_byte_record(&mut skipped) {
+                break;
+            }
+            found = Some(found.map_or(0, |record| record + 1));
+        }
+        // the line is past the end of the file, unless it is the last line and has no line ending
+        let end = reader.position().line();
+        if line < end || (line == end && !ends_with_newline) {
+            found
+        } else {
+            None
+        }
+    }
+
     /// Gets the size of the file in megabytes to 2 decimal places.
     ///
     /// # Example
@@ -114,7 +386,8 @@ pub mod reader {
     /// # Examples
     /// ```
     /// use csv::StringRecord;
-    /// let file_path = Option::from(String::from("test_uspop.csv"));
+    /// use csv_viewer::reader::reader::Dialect;
+    /// let file_path = Option::from(String::from("tests/test_uspop.csv"));
     /// let pos = 3; // starts at index 0, skips one line for header
     /// let rows_to_display = 4;
     /// let test_records: Vec<StringRecord> = vec![
@@ -124,59 +397,201 @@ pub mod reader {
     ///     StringRecord::from(vec!["Shadow Oaks Addition", "AR", "", "34.9555556", "-91.9475000"]),
     /// ];
     ///
-    /// let result_records = csv_viewer::reader::reader::get_records_from_pos(file_path, pos, rows_to_display, true);
+    /// let result_records = csv_viewer::reader::reader::get_records_from_pos(file_path.clone(), pos, rows_to_display, true, &Dialect::default());
     /// assert_eq!(test_records, result_records);
+    ///
+    /// // without a header row, the first record is row 0
+    /// let first_records = csv_viewer::reader::reader::get_records_from_pos(file_path, 0, 1, false, &Dialect::default());
+    /// assert_eq!(first_records[0].get(0), Some("City"));
     /// ```
     pub fn get_records_from_pos(
         file_path: Option<String>,
         pos: usize,
         num_of_rows_to_display: usize,
         has_header: bool,
-        delimiter: char,
+        dialect: &Dialect,
     ) -> Vec<StringRecord> {
         let file = File::open(file_path.unwrap()).unwrap();
-        let mut reader = BufReader::new(file);
+        let reader = BufReader::new(file);
         let mut buffer = Vec::new();
-        let mut lines_read = 0;
-
-        // skip header
-        if has_header && (pos == 0) {
-            let mut line = String::new();
-            reader.read_line(&mut line).unwrap();
-            lines_read = 1
-        }
-
-        // create CSV reader+
 
-        let mut csv_reader = ReaderBuilder::new()
-            .delimiter(u8::try_from(delimiter).unwrap())
-            .has_headers(!has_header)
+        // create CSV reader, header rows are skipped below with the rest of the records
+        let mut csv_reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
             .from_reader(reader);
 
-        // skip to starting position
-        for _ in 0..pos {
+        // skip to starting position, stepping over the header if the file has one
+        let records_to_skip = if has_header { pos + 1 } else { pos };
+        for _ in 0..records_to_skip {
             let mut record = StringRecord::new();
-            let bytes_read = csv_reader.read_record(&mut record).unwrap();
-            if bytes_read == false {
-                break;
+            match csv_reader.read_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => {}
+                Err(e) => print_record_error(&e),
             }
         }
 
-        // read line forward by 1 if file has header and not reading from pos 0
-        if has_header && (pos != 0) {
-            let mut record = StringRecord::new();
-            csv_reader.read_record(&mut record).unwrap();
+        // read records into buffer, a record that cannot be read is kept as an empty row so the
+        // rows after it stay in place
+        for result in csv_reader.records() {
+            match result {
+                Ok(record) => buffer.push(record),
+                Err(e) => {
+                    print_record_error(&e);
+                    buffer.push(StringRecord::default());
+                }
+            }
+            if buffer.len() >= num_of_rows_to_display {
+                break;
+            }
         }
+        buffer
+    }
 
-        // read records into buffer
+    /// Builds a vector of String Records in the same way as `get_records_from_pos`, but seeks
+    /// directly to `pos` using the byte offsets stored in `index` instead of parsing every
+    /// record before it.
+    ///
+    /// # Examples
+    /// ```
+    /// use csv_viewer::index::index::RowIndex;
+    /// use csv_viewer::reader::reader::{get_records_from_index, get_records_from_pos, Dialect};
+    ///
+    /// let file_path = Option::from(String::from("tests/test_uspop.csv"));
+    /// let dialect = Dialect::default();
+    /// let index = RowIndex::build("tests/test_uspop.csv", &dialect, 16).unwrap();
+    ///
+    /// for pos in [0, 3, 15, 16, 150, 195] {
+    ///     assert_eq!(
+    ///         get_records_from_index(file_path.clone(), &index, pos, 10, true, &dialect),
+    ///         get_records_from_pos(file_path.clone(), pos, 10, true, &dialect),
+    ///     );
+    /// }
+    ///
+    /// // a record that cannot be read is an empty row, so the rows after it keep their place
+    /// let path = std::env::temp_dir().join("csv_viewer_unreadable_row.csv").display().to_string();
+    /// std::fs::write(&path, b"name\na\n\xff\nb\nc\n").unwrap();
+    /// let index = RowIndex::build(&path, &dialect, 2).unwrap();
+    /// let rows = get_records_from_index(Some(path.clone()), &index, 0, 10, true, &dialect);
+    /// assert_eq!(rows, get_records_from_pos(Some(path.clone()), 0, 10, true, &dialect));
+    /// assert_eq!(rows.len(), 4);
+    /// assert!(rows[1].is_empty());
+    /// assert_eq!(rows[2].get(0), Some("b"));
+    /// let rows = get_records_from_index(Some(path), &index, 2, 10, true, &dialect);
+    /// assert_eq!(rows[0].get(0), Some("b"));
+    /// ```
+    pub fn get_records_from_index(
+        file_path: Option<String>,
+        index: &RowIndex,
+        pos: usize,
+        num_of_rows_to_display: usize,
+        has_header: bool,
+        dialect: &Dialect,
+    ) -> Vec<StringRecord> {
+        let mut buffer = Vec::new();
+        // the index counts the header as a record
+        let record = if has_header { pos + 1 } else { pos };
+
+        let mut csv_reader = match get_reader_at_record(&file_path.unwrap(), index, record, dialect)
+        {
+            Ok(Some(reader)) => reader,
+            Ok(None) => return buffer,
+            Err(e) => {
+                println!("Error: Cannot Seek to Record {}: {}", record, e);
+                return buffer;
+            }
+        };
+
+        // read records into buffer, keeping rows in place as `get_records_from_pos` does
         for result in csv_reader.records() {
-            if let Ok(record) = result {
-                buffer.push(record.clone());
-                if buffer.len() >= num_of_rows_to_display {
-                    break;
+            match result {
+                Ok(record) => buffer.push(record),
+                Err(e) => {
+                    print_record_error(&e);
+                    buffer.push(StringRecord::default());
                 }
             }
+            if buffer.len() >= num_of_rows_to_display {
+                break;
+            }
         }
         buffer
     }
+
+    /// Reads the data rows numbered in `rows`, in the order given, returning one entry per row.
+    /// Each row is found by seeking with the byte offsets stored in `index`, reusing one reader.
+    /// A row that is out of range or cannot be read is `None`, so the records stay in line with
+    /// `rows`.
+    ///
+    /// # Examples
+    /// ```
+    /// use csv_viewer::index::index::RowIndex;
+    /// use csv_viewer::reader::reader::{get_records_from_rows, Dialect};
+    ///
+    /// let file_path = Option::from(String::from("tests/test_uspop.csv"));
+    /// let dialect = Dialect::default();
+    /// let index = RowIndex::build("tests/test_uspop.csv", &dialect, 16).unwrap();
+    ///
+    /// let records = get_records_from_rows(file_path, &index, &[5, 500, 1], true, &dialect);
+    /// assert_eq!(records.len(), 3);
+    /// assert_eq!(records[0].as_ref().unwrap().get(0), Some("Selma"));
+    /// assert!(records[1].is_none());
+    /// assert_eq!(records[2].as_ref().unwrap().get(0), Some("Kenai"));
+    /// ```
+    pub fn get_records_from_rows(
+        file_path: Option<String>,
+        index: &RowIndex,
+        rows: &[usize],
+        has_header: bool,
+        dialect: &Dialect,
+    ) -> Vec<Option<StringRecord>> {
+        let mut csv_reader = match dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(file_path.unwrap())
+        {
+            Ok(reader) => reader,
+            Err(e) => {
+                println!("Error: Cannot Open File: {}", e);
+                return vec![None; rows.len()];
+            }
+        };
+        rows.iter()
+            .map(|row| {
+                // the index counts the header as a record
+                let record = if has_header { row + 1 } else { *row };
+                match read_record_at(&mut csv_reader, index, record) {
+                    Ok(found) => found,
+                    Err(e) => {
+                        println!("Error: Cannot Read Record {}: {}", record, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Seeks `reader` to record number `record` using `index`, and reads it.
+    /// Returns `None` if the file has no such record.
+    fn read_record_at(
+        reader: &mut Reader<File>,
+        index: &RowIndex,
+        record: usize,
+    ) -> Result<Option<StringRecord>, csv::Error> {
+        let (pos, skip) = match index.locate(record) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        reader.seek(pos)?;
+        let mut found = StringRecord::new();
+        for _ in 0..=skip {
+            if !reader.read_record(&mut found)? {
+                return Ok(None);
+            }
+        }
+        Ok(Some(found))
+    }
 }
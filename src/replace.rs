@@ -0,0 +1,198 @@
+pub mod replace {
+    use csv::StringRecord;
+    use regex::{NoExpand, Regex};
+    use std::borrow::Cow;
+    use std::error::Error;
+    use std::fs::canonicalize;
+
+    use crate::find::find::FindQuery;
+    use crate::reader::reader::Dialect;
+
+    /// A cell changed by a replacement, with its text before and after.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct CellChange {
+        pub row: usize,
+        pub column: usize,
+        pub before: String,
+        pub after: String,
+    }
+
+    /// Returns `cell` with every match of `regex`, the compiled form of `query`, replaced by
+    /// `replacement`. When the query is a regular expression the replacement may refer to its
+    /// capture groups as `$1` or `${name}`, otherwise the replacement is inserted as it is.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::FindQuery;
+    /// use csv_viewer::replace::replace::replace_in_cell;
+    ///
+    /// let query = FindQuery { regex: true, ..FindQuery::new(r"(\d+)/(\d+)") };
+    /// let regex = query.compile().unwrap();
+    /// assert_eq!(replace_in_cell("due 4/12", &regex, &query, "$2-$1"), "due 12-4");
+    ///
+    /// let query = FindQuery::new("$");
+    /// let regex = query.compile().unwrap();
+    /// assert_eq!(replace_in_cell("$5", &regex, &query, "$1 USD "), "$1 USD 5");
+    /// ```
+    pub fn replace_in_cell<'a>(
+        cell: &'a str,
+        regex: &Regex,
+        query: &FindQuery,
+        replacement: &str,
+    ) -> Cow<'a, str> {
+        if query.regex {
+            regex.replace_all(cell, replacement)
+        } else {
+            regex.replace_all(cell, NoExpand(replacement))
+        }
+    }
+
+    /// Replaces the matches within the cells of `record` searched by `query`.
+    /// Returns the changed cells, numbered as row `row`, or nothing if no cell changed.
+    pub fn replace_in_record(
+        record: &mut StringRecord,
+        row: usize,
+        regex: &Regex,
+        query: &FindQuery,
+        replacement: &str,
+    ) -> Vec<CellChange> {
+        let mut changes: Vec<CellChange> = Vec::new();
+        for (column, cell) in record.iter().enumerate() {
+            if !query.searches_column(column) {
+                continue;
+            }
+            if let Cow::Owned(after) = replace_in_cell(cell, regex, query, replacement) {
+                if after != cell {
+                    changes.push(CellChange {
+                        row,
+                        column,
+                        before: cell.to_string(),
+                        after,
+                    });
+                }
+            }
+        }
+        if !changes.is_empty() {
+            let mut replaced = StringRecord::new();
+            for (column, cell) in record.iter().enumerate() {
+                match changes.iter().find(|change| change.column == column) {
+                    Some(change) => replaced.push_field(&change.after),
+                    None => replaced.push_field(cell),
+                }
+            }
+            *record = replaced;
+        }
+        changes
+    }
+
+    /// Returns up to `limit` of the cells that replacing `query` with `replacement` would change,
+    /// without writing anything. Rows are numbered as data rows, as they are by `find`.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::FindQuery;
+    /// use csv_viewer::reader::reader::Dialect;
+    /// use csv_viewer::replace::replace::preview_replacements;
+    ///
+    /// let query = FindQuery { whole_cell: true, columns: vec![1], ..FindQuery::new("AL") };
+    /// let changes = preview_replacements(
+    ///     "tests/test_uspop.csv".to_string(), &query, "Alabama", true, &Dialect::default(), 2,
+    /// ).unwrap();
+    /// assert_eq!(changes.len(), 2);
+    /// assert_eq!(changes[0].row, 2);
+    /// assert_eq!((changes[0].before.as_str(), changes[0].after.as_str()), ("AL", "Alabama"));
+    /// ```
+    pub fn preview_replacements(
+        file_path: String,
+        query: &FindQuery,
+        replacement: &str,
+        has_headers: bool,
+        dialect: &Dialect,
+        limit: usize,
+    ) -> Result<Vec<CellChange>, Box<dyn Error>> {
+        let regex = query.compile()?;
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(has_headers)
+            .flexible(true)
+            .from_path(file_path)?;
+
+        let mut changes: Vec<CellChange> = Vec::new();
+        let mut record = StringRecord::new();
+        let mut row = 0;
+        while changes.len() < limit && reader.read_record(&mut record)? {
+            changes.extend(replace_in_record(
+                &mut record,
+                row,
+                &regex,
+                query,
+                replacement,
+            ));
+            row += 1;
+        }
+        changes.truncate(limit);
+        Ok(changes)
+    }
+
+    /// Replaces every match of `query` in the file at `file_path` with `replacement`, writing the
+    /// result to `output_path` in the same dialect. The header row is copied as it is.
+    /// Returns the number of cells changed.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::find::find::FindQuery;
+    /// use csv_viewer::reader::reader::Dialect;
+    /// use csv_viewer::replace::replace::replace_to_file;
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let input = dir.join("csv_viewer_replace_in.csv").display().to_string();
+    /// let output = dir.join("csv_viewer_replace_out.csv").display().to_string();
+    /// std::fs::write(&input, "sku;note\nA-1;ok #TMP\nB-2;\"#TMP; fragile\"\n").unwrap();
+    ///
+    /// let query = FindQuery { regex: true, columns: vec![1], ..FindQuery::new(r"\s*#TMP;?\s*") };
+    /// let dialect = Dialect::with_delimiter(';');
+    /// let changed = replace_to_file(input, output.clone(), &query, "", true, &dialect).unwrap();
+    /// assert_eq!(changed, 2);
+    /// assert_eq!(std::fs::read_to_string(&output).unwrap(), "sku;note\nA-1;ok\nB-2;fragile\n");
+    /// ```
+    pub fn replace_to_file(
+        file_path: String,
+        output_path: String,
+        query: &FindQuery,
+        replacement: &str,
+        has_headers: bool,
+        dialect: &Dialect,
+    ) -> Result<usize, Box<dyn Error>> {
+        // Writing the output would truncate the file still being read
+        if let (Ok(input), Ok(output)) = (canonicalize(&file_path), canonicalize(&output_path)) {
+            if input == output {
+                return Err(From::from("Cannot replace into the file being read"));
+            }
+        }
+        let regex = query.compile()?;
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(file_path)?;
+        let mut writer = dialect
+            .writer_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(output_path)?;
+
+        let mut record = StringRecord::new();
+        if has_headers && reader.read_record(&mut record)? {
+            writer.write_record(&record)?;
+        }
+        let mut changed = 0;
+        let mut row = 0;
+        while reader.read_record(&mut record)? {
+            changed += replace_in_record(&mut record, row, &regex, query, replacement).len();
+            writer.write_record(&record)?;
+            row += 1;
+        }
+        writer.flush()?;
+        Ok(changed)
+    }
+}
@@ -0,0 +1,254 @@
+pub mod sniff {
+    use csv::StringRecord;
+    use std::error::Error;
+    use std::fs::File;
+    use std::io::Read;
+
+    use crate::reader::reader::{Dialect, LineEnding};
+
+    /// Number of bytes read from the head of a file when sniffing its dialect.
+    const SAMPLE_SIZE: u64 = 64 * 1024;
+    /// Maximum number of records from the sample compared against one another.
+    const SAMPLE_RECORDS: usize = 100;
+
+    /// Delimiters tried by the sniffer, in order of preference when several fit equally well.
+    const CANDIDATE_DELIMITERS: [u8; 5] = [b',', b'\t', b';', b'|', b':'];
+    const CANDIDATE_QUOTES: [u8; 2] = [b'"', b'\''];
+
+    /// The dialect detected by the sniffer, and whether the first record looks like a header row.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Sniffed {
+        pub dialect: Dialect,
+        pub has_headers: bool,
+    }
+
+    /// Reads the head of the file at `file_path` and guesses its dialect and header presence.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::sniff::sniff::sniff_file;
+    ///
+    /// let sniffed = sniff_file("tests/test_uspop.csv").unwrap();
+    /// assert_eq!(sniffed.dialect.delimiter, b',');
+    /// assert!(sniffed.has_headers);
+    ///
+    /// let sniffed = sniff_file("semicol.csv").unwrap();
+    /// assert_eq!(sniffed.dialect.delimiter, b';');
+    /// assert!(sniffed.has_headers);
+    /// ```
+    pub fn sniff_file(file_path: &str) -> Result<Sniffed, Box<dyn Error>> {
+        let mut sample = Vec::new();
+        File::open(file_path)?
+            .take(SAMPLE_SIZE)
+            .read_to_end(&mut sample)?;
+        let truncated = sample.len() as u64 == SAMPLE_SIZE;
+        Ok(sniff_sample(&sample, truncated))
+    }
+
+    /// Guesses the dialect and header presence of a sample of delimited data.
+    /// If `truncated` is set, the sample was cut from a longer file, so its last line is ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use csv_viewer::reader::reader::LineEnding;
+    /// use csv_viewer::sniff::sniff::sniff_sample;
+    ///
+    /// let sniffed = sniff_sample(b"name;price\n'Tea; green';1,50\nCoffee;2,10\n", false);
+    /// assert_eq!(sniffed.dialect.delimiter, b';');
+    /// assert_eq!(sniffed.dialect.quote, b'\'');
+    /// assert!(sniffed.has_headers);
+    ///
+    /// let sniffed = sniff_sample(b"1|2|3\r\n4|5|6\r\n7|8|9\r\n", false);
+    /// assert_eq!(sniffed.dialect.delimiter, b'|');
+    /// assert_eq!(sniffed.dialect.terminator, LineEnding::Crlf);
+    /// assert!(!sniffed.has_headers);
+    ///
+    /// let sniffed = sniff_sample(b"id\tnote\n1\t\"say \\\"hi\\\" now\"\n", false);
+    /// assert_eq!(sniffed.dialect.delimiter, b'\t');
+    /// assert_eq!(sniffed.dialect.escape, Some(b'\\'));
+    ///
+    /// // an empty quoted cell is not a doubled quote
+    /// let sniffed = sniff_sample(b"id,note,tag\n1,\"say \\\"hi\\\"\",\"\"\n2,\"\\\"x\\\"\",b\n", false);
+    /// assert_eq!(sniffed.dialect.escape, Some(b'\\'));
+    /// ```
+    pub fn sniff_sample(sample: &[u8], truncated: bool) -> Sniffed {
+        let mut sample = sample;
+        if truncated {
+            if let Some(end) = sample.iter().rposition(|b| *b == b'\n') {
+                sample = &sample[..end + 1];
+            }
+        }
+
+        let mut dialect = Dialect {
+            quote: sniff_quote(sample),
+            terminator: sniff_terminator(sample),
+            ..Default::default()
+        };
+        let escaped = [b'\\', dialect.quote];
+        if contains(sample, &escaped) && !has_doubled_quote(sample, dialect.quote) {
+            dialect.escape = Some(b'\\');
+            dialect.double_quote = false;
+        }
+
+        // pick the delimiter that splits the most records into the same number of fields
+        let mut best_score = 0.0;
+        let mut best_records: Vec<StringRecord> = Vec::new();
+        for delimiter in CANDIDATE_DELIMITERS {
+            let candidate = Dialect {
+                delimiter,
+                ..dialect.clone()
+            };
+            let records = read_sample_records(sample, &candidate);
+            let score = get_consistency(&records);
+            if score > best_score {
+                best_score = score;
+                best_records = records;
+                dialect.delimiter = delimiter;
+            }
+        }
+
+        Sniffed {
+            has_headers: sniff_has_headers(&best_records),
+            dialect,
+        }
+    }
+
+    /// Returns the candidate quote character that most often opens a field.
+    fn sniff_quote(sample: &[u8]) -> u8 {
+        let mut best_quote = CANDIDATE_QUOTES[0];
+        let mut best_count = 0;
+        for quote in CANDIDATE_QUOTES {
+            let mut count = 0;
+            for (i, byte) in sample.iter().enumerate() {
+                let opens_field = i == 0
+                    || CANDIDATE_DELIMITERS.contains(&sample[i - 1])
+                    || sample[i - 1] == b'\n'
+                    || sample[i - 1] == b'\r';
+                if *byte == quote && opens_field {
+                    count += 1;
+                }
+            }
+            if count > best_count {
+                best_count = count;
+                best_quote = quote;
+            }
+        }
+        best_quote
+    }
+
+    /// Returns the line ending used by the sample, so that rewritten files keep it.
+    fn sniff_terminator(sample: &[u8]) -> LineEnding {
+        if contains(sample, b"\r\n") {
+            LineEnding::Crlf
+        } else if sample.contains(&b'\r') && !sample.contains(&b'\n') {
+            LineEnding::Cr
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Returns true if a quoted field of the sample holds a quote written twice, as in
+    /// `"say ""hi"""`. An empty quoted field, `""`, is not counted, nor is a quote escaped with
+    /// a backslash.
+    fn has_doubled_quote(sample: &[u8], quote: u8) -> bool {
+        let mut in_quotes = false;
+        let mut at_field_start = true;
+        let mut i = 0;
+        while i < sample.len() {
+            let b = sample[i];
+            if !in_quotes {
+                in_quotes = b == quote && at_field_start;
+                at_field_start = CANDIDATE_DELIMITERS.contains(&b) || b == b'\n' || b == b'\r';
+            } else if b == b'\\' {
+                // skip the escaped character
+                i += 1;
+            } else if b == quote {
+                if sample.get(i + 1) == Some(&quote) {
+                    return true;
+                }
+                in_quotes = false;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Returns true if `needle` appears anywhere within `haystack`.
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    /// Parses up to `SAMPLE_RECORDS` records of the sample using `dialect`.
+    fn read_sample_records(sample: &[u8], dialect: &Dialect) -> Vec<StringRecord> {
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(sample);
+        reader
+            .records()
+            .take(SAMPLE_RECORDS)
+            .filter_map(|result| result.ok())
+            .collect()
+    }
+
+    /// Returns the fraction of records that have the most common field count.
+    /// Records split into a single field don't count, as the delimiter never appeared in them.
+    fn get_consistency(records: &[StringRecord]) -> f64 {
+        let mut counts: Vec<(usize, usize)> = Vec::new();
+        for record in records {
+            match counts.iter_mut().find(|(len, _)| *len == record.len()) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((record.len(), 1)),
+            }
+        }
+        match counts.iter().max_by_key(|(_, n)| *n) {
+            Some((len, n)) if *len > 1 => *n as f64 / records.len() as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Guesses whether the first record is a header by comparing it to the records below it.
+    /// A column votes for a header when its first cell is text above numbers, or differs in
+    /// length from a column of fixed-length values. Ties are treated as having a header, as
+    /// most files do.
+    fn sniff_has_headers(records: &[StringRecord]) -> bool {
+        if records.len() < 2 {
+            return true;
+        }
+        let header = &records[0];
+        let mut votes = 0;
+        for (column, title) in header.iter().enumerate() {
+            let cells: Vec<&str> = records[1..]
+                .iter()
+                .filter_map(|record| record.get(column))
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+            if cells.iter().all(|cell| is_numeric(cell)) {
+                if is_numeric(title) {
+                    votes -= 1;
+                } else {
+                    votes += 1;
+                }
+            } else if cells.iter().all(|cell| cell.len() == cells[0].len()) {
+                if title.len() == cells[0].len() {
+                    votes -= 1;
+                } else {
+                    votes += 1;
+                }
+            }
+        }
+        votes >= 0
+    }
+
+    /// Returns true if the cell holds a number, allowing a decimal comma.
+    fn is_numeric(cell: &str) -> bool {
+        let cell = cell.trim();
+        cell.parse::<f64>().is_ok() || cell.replacen(',', ".", 1).parse::<f64>().is_ok()
+    }
+}
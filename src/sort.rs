@@ -1,100 +1,472 @@
 pub mod sort {
-    use csv::{ReaderBuilder, StringRecord, WriterBuilder};
-    use std::borrow::Borrow;
+    use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+    use std::cmp::Ordering;
+    use std::collections::BinaryHeap;
     use std::error::Error;
-    use std::fs::{remove_file, File, OpenOptions};
-    use std::io::{Write};
+    use std::fs::{canonicalize, remove_file, File};
+    use std::mem::size_of;
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
     use sysinfo::{System, SystemExt};
 
-    /// Sorts the records from the data stored at `file_path` and exports sorted data to `output_path`
+    use crate::compare::compare::{compare_cells, CompareOptions, CompareType};
+    use crate::reader::reader::Dialect;
+
+    /// A column to sort by, with the direction and type of comparison to sort it with.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SortKey {
+        pub column: usize,
+        pub ascending: bool,
+        pub compare: CompareType,
+        pub options: CompareOptions,
+    }
+
+    impl SortKey {
+        /// Returns a key sorting `column` in ascending lexical order.
+        pub fn new(column: usize) -> SortKey {
+            SortKey {
+                column,
+                ascending: true,
+                compare: CompareType::Lexical,
+                options: CompareOptions::default(),
+            }
+        }
+    }
+
+    /// Counts the sorts started by this process, so that concurrent sorts use different run files.
+    static SORT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    /// Sorts the records from the data stored at `file_path` and exports sorted data to `output_path`.
+    /// Records are ordered by the first of the `keys`, then by the next key where they are equal,
+    /// and so on.
+    /// The input is parsed with `dialect`, and the output written with `output_dialect`, which may
+    /// be the same dialect or a different one to convert the file while sorting it.
+    /// If `has_headers` is set the first record is kept at the top as the header, otherwise it is
+    /// sorted along with the rest of the records.
+    /// A record that cannot be read, e.g. one that is not valid UTF-8, fails the sort with its
+    /// error rather than being left out.
+    ///
+    /// Files too large to sort in a quarter of the available memory are sorted with an external
+    /// merge sort, see `sort_records_in_chunks`.
+    ///
+    /// # Example
+    /// A data row that repeats the header is sorted like any other row.
+    /// ```
+    /// use csv_viewer::reader::reader::Dialect;
+    /// use csv_viewer::sort::sort::{sort_records, SortKey};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let input = dir.join("csv_viewer_sort_dialect_in.csv").display().to_string();
+    /// let output = dir.join("csv_viewer_sort_dialect_out.csv").display().to_string();
+    /// std::fs::write(&input, "name;rank\r\nzeta;3\r\nname;rank\r\n\"a;b\";1\r\n").unwrap();
+    ///
+    /// let semicolon = Dialect::with_delimiter(';');
+    /// let tab = Dialect::with_delimiter('\t');
+    /// sort_records(input.clone(), output.clone(), &[SortKey::new(0)], &semicolon, true, &tab).unwrap();
+    /// let sorted = std::fs::read_to_string(&output).unwrap();
+    /// assert_eq!(sorted, "name\trank\na;b\t1\nname\trank\nzeta\t3\n");
+    ///
+    /// std::fs::write(&input, b"name;rank\nzeta;3\n\xff;1\n").unwrap();
+    /// assert!(sort_records(input, output, &[SortKey::new(0)], &semicolon, true, &tab).is_err());
+    /// ```
     pub fn sort_records(
         file_path: String,
         output_path: String,
-        field_index: usize,
+        keys: &[SortKey],
+        dialect: &Dialect,
+        has_headers: bool,
+        output_dialect: &Dialect,
     ) -> Result<(), Box<dyn Error>> {
-        match remove_file(output_path.clone()) {
-            Ok(()) => println!("File successfully deleted."),
-            Err(e) => println!("File not found: {}", output_path),
-        }
-
-        // Open the CSV file
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(file_path.clone())?;
-        let file_size = file.metadata()?.len();
-        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
-        let mut h_test = ReaderBuilder::new()
-            .has_headers(true)
-            .from_path(file_path.clone())?;
+        // Calculate the chunk size based on available memory
+        let available_memory = System::new_all().available_memory();
+        let chunk_bytes = (available_memory / 4) as usize;
+
+        sort_records_in_chunks(
+            file_path,
+            output_path,
+            keys,
+            dialect,
+            has_headers,
+            output_dialect,
+            chunk_bytes,
+        )?;
+        Ok(())
+    }
+
+    /// Sorts the records of `file_path` into `output_path` using at most roughly `chunk_bytes` of
+    /// memory for records.
+    ///
+    /// Records are read in chunks of up to `chunk_bytes`, and each chunk is sorted and spilled
+    /// to a temporary run file. The runs are then merged into the output, holding only one record
+    /// from each run in memory at a time. If the whole file fits in one chunk it is written
+    /// straight to the output instead. Records with equal keys keep their original order.
+    ///
+    /// Returns the number of sorted runs the file was split into.
+    ///
+    /// # Example
+    /// This generates a file too large to sort in a single 4KB chunk, and checks the merged
+    /// output is sorted as a whole.
+    /// ```
+    /// use csv_viewer::reader::reader::Dialect;
+    /// use csv_viewer::sort::sort::{sort_records_in_chunks, SortKey};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let input = dir.join("csv_viewer_merge_sort_in.csv").display().to_string();
+    /// let output = dir.join("csv_viewer_merge_sort_out.csv").display().to_string();
+    ///
+    /// let mut contents = String::from("id,key\n");
+    /// let mut seed: u64 = 42;
+    /// for id in 0..2000 {
+    ///     seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///     contents.push_str(&format!("{},{:05}\n", id, (seed >> 33) % 10000));
+    /// }
+    /// std::fs::write(&input, contents).unwrap();
+    ///
+    /// let dialect = Dialect::default();
+    /// let keys = [SortKey::new(1)];
+    /// let runs = sort_records_in_chunks(input, output.clone(), &keys, &dialect, true, &dialect, 4096).unwrap();
+    /// assert!(runs > 5);
+    ///
+    /// let mut reader = csv::Reader::from_path(&output).unwrap();
+    /// assert_eq!(reader.headers().unwrap(), vec!["id", "key"]);
+    /// let keys: Vec<String> = reader
+    ///     .records()
+    ///     .map(|record| record.unwrap()[1].to_string())
+    ///     .collect();
+    /// assert_eq!(keys.len(), 2000);
+    /// assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+    /// ```
+    pub fn sort_records_in_chunks(
+        file_path: String,
+        output_path: String,
+        keys: &[SortKey],
+        dialect: &Dialect,
+        has_headers: bool,
+        output_dialect: &Dialect,
+        chunk_bytes: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        // Writing the output would truncate the file still being read
+        if let (Ok(input), Ok(output)) = (canonicalize(&file_path), canonicalize(&output_path)) {
+            if input == output {
+                return Err(From::from("Cannot sort a file into itself"));
+            }
+        }
+
+        // Open the CSV file. The header is read as a plain record, so that data rows matching it
+        // are still sorted.
+        let mut rdr = dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(file_path)?;
+        let mut header = StringRecord::new();
+        let has_headers = has_headers && rdr.read_record(&mut header)?;
+
+        let mut runs = RunFiles::new();
+        let mut chunk: Vec<StringRecord> = Vec::new();
+        let mut chunk_size = 0;
+        let mut record = StringRecord::new();
+        // A record that cannot be read fails the sort, as leaving it out would lose a row
+        while rdr.read_record(&mut record)? {
+            chunk_size += get_record_size(&record);
+            chunk.push(record.clone());
+
+            // Spill the chunk once it reaches the memory budget
+            if chunk_size >= chunk_bytes {
+                sort_chunk(&mut chunk, keys);
+                runs.write_run(&chunk)?;
+                chunk.clear();
+                chunk_size = 0;
+            }
+        }
+        sort_chunk(&mut chunk, keys);
 
         // Create a new sorted CSV file
-        let sorted_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(output_path)?;
-        let mut wtr = WriterBuilder::new()
-            .has_headers(true)
-            .from_writer(sorted_file);
+        let mut wtr = output_dialect
+            .writer_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(output_path)?;
 
         // Write the CSV header to the new file
-        let header = rdr.headers()?;
-        wtr.write_byte_record(&header.as_byte_record())?;
+        if has_headers {
+            wtr.write_record(&header)?;
+        }
 
-        // Calculate the chunk size based on available memory and file size
-        let available_memory = System::new_all().available_memory();
-        let chunk_size = (available_memory / 4) as usize;
-        let num_chunks = (file_size as usize / chunk_size) + 1;
-
-        // new header to reference, workaround to avoid duplicating the header inside the file.
-        let h = h_test.headers()?;
-
-        // Sort the file in chunks
-        for chunk_index in 0..num_chunks {
-            // Seek to the beginning of the chunk
-            let chunk_start = chunk_index * chunk_size;
-            // let mut position: Position = Position::new();
-            // position.set_record(chunk_start as u64);
-            // rdr.seek(position.clone())?;
-            // println!("{:?}", position);
-
-            // Read the chunk into memory
-            let mut chunk: Vec<StringRecord> = Vec::new();
-
-            for row in 0..chunk_size {
-                let mut record: StringRecord = StringRecord::new();
-                match rdr.read_record(&mut record) {
-                    Ok(false) => break,
-                    Ok(_) => {
-                        if !matches(&record, &h) {
-                            chunk.push(record);
-                        }
-                    }
-                    Err(e) => println!("Error: Cannot Read Chunk"),
-                }
+        if runs.paths.is_empty() {
+            // Everything fit in memory, so there is nothing to merge
+            for record in chunk.iter() {
+                wtr.write_record(record)?;
             }
+            wtr.flush()?;
+            return Ok(1);
+        }
 
-            // Sort the records by field index
-            chunk.sort_by_key(|record| record.get(field_index).unwrap().to_string());
+        if !chunk.is_empty() {
+            runs.write_run(&chunk)?;
+        }
+        drop(chunk);
+        merge_runs(&runs, &mut wtr, keys)?;
+        Ok(runs.paths.len())
+    }
+
+    /// An ordering of the data rows of a file, for viewing the file sorted without rewriting it.
+    /// Row numbers count data rows from 0, not including the header.
+    #[derive(Debug, Clone)]
+    pub struct RowPermutation {
+        pub keys: Vec<SortKey>,
+        rows: Vec<usize>,
+        // position in the sorted view of each file row, the inverse of `rows`
+        positions: Vec<usize>,
+    }
 
-            // Write the sorted chunk to the new file
-            for record in chunk.into_iter() {
-                wtr.write_record(record.into_iter())?;
+    impl RowPermutation {
+        /// Reads the file at `file_path` and orders its data rows by `keys`. Only the cells of the
+        /// key columns are held in memory while sorting. Rows with equal keys keep their order.
+        /// Fails if a record cannot be read, as every row after it would be numbered wrongly.
+        ///
+        /// # Example
+        /// ```
+        /// use csv_viewer::compare::compare::CompareType;
+        /// use csv_viewer::reader::reader::Dialect;
+        /// use csv_viewer::sort::sort::{RowPermutation, SortKey};
+        ///
+        /// let key = SortKey { ascending: false, compare: CompareType::Numeric, ..SortKey::new(2) };
+        /// let order = RowPermutation::build("tests/test_uspop.csv", vec![key], &Dialect::default(), true).unwrap();
+        /// assert_eq!(order.len(), 201);
+        /// // Indianapolis has the largest population, and appears at data rows 38 and 139
+        /// assert_eq!(order.get_range(0, 2), &[38, 139]);
+        /// assert_eq!(order.position_of(139), Some(1));
+        /// ```
+        pub fn build(
+            file_path: &str,
+            keys: Vec<SortKey>,
+            dialect: &Dialect,
+            has_headers: bool,
+        ) -> Result<RowPermutation, Box<dyn Error>> {
+            let mut rdr = dialect
+                .reader_builder()
+                .has_headers(has_headers)
+                .flexible(true)
+                .from_path(file_path)?;
+
+            // keep only the key cells of each row, with the keys renumbered to match
+            let cell_keys: Vec<SortKey> = keys
+                .iter()
+                .enumerate()
+                .map(|(column, key)| SortKey {
+                    column,
+                    ..key.clone()
+                })
+                .collect();
+            let mut cells: Vec<StringRecord> = Vec::new();
+            let mut record = StringRecord::new();
+            while rdr.read_record(&mut record)? {
+                cells.push(
+                    keys.iter()
+                        .map(|key| record.get(key.column).unwrap_or(""))
+                        .collect(),
+                );
             }
-            wtr.flush()?;
+
+            let mut rows: Vec<usize> = (0..cells.len()).collect();
+            rows.sort_by(|a, b| compare_records(&cells[*a], &cells[*b], &cell_keys));
+            let mut positions = vec![0; rows.len()];
+            for (pos, row) in rows.iter().enumerate() {
+                positions[*row] = pos;
+            }
+            Ok(RowPermutation {
+                keys,
+                rows,
+                positions,
+            })
+        }
+
+        /// Returns the number of rows in the ordering.
+        pub fn len(&self) -> usize {
+            self.rows.len()
+        }
+
+        /// Returns true if the file had no data rows to order.
+        pub fn is_empty(&self) -> bool {
+            self.rows.is_empty()
         }
 
+        /// Returns the file row shown at `pos` of the sorted view.
+        pub fn get(&self, pos: usize) -> Option<usize> {
+            self.rows.get(pos).copied()
+        }
+
+        /// Returns the file rows shown at `pos` and up to `n` rows after it in the sorted view.
+        pub fn get_range(&self, pos: usize, n: usize) -> &[usize] {
+            let start = pos.min(self.rows.len());
+            let end = pos.saturating_add(n).min(self.rows.len());
+            &self.rows[start..end]
+        }
+
+        /// Returns the position in the sorted view of file row `row`.
+        pub fn position_of(&self, row: usize) -> Option<usize> {
+            self.positions.get(row).copied()
+        }
+    }
+
+    /// Sorts the records of a chunk by `keys`, keeping equal records in order.
+    fn sort_chunk(chunk: &mut [StringRecord], keys: &[SortKey]) {
+        chunk.sort_by(|a, b| compare_records(a, b, keys));
+    }
+
+    /// Compares two records by each of the `keys` in turn. A missing field sorts as empty.
+    ///
+    /// # Example
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use csv::StringRecord;
+    /// use csv_viewer::compare::compare::CompareType;
+    /// use csv_viewer::sort::sort::{compare_records, SortKey};
+    ///
+    /// let keys = vec![
+    ///     SortKey::new(1),
+    ///     SortKey { ascending: false, compare: CompareType::Numeric, ..SortKey::new(2) },
+    /// ];
+    /// let selma = StringRecord::from(vec!["Selma", "AL", "18980"]);
+    /// let oakman = StringRecord::from(vec!["Oakman", "AL", "9"]);
+    /// let sandfort = StringRecord::from(vec!["Sandfort", "AL", ""]);
+    /// let kenai = StringRecord::from(vec!["Kenai", "AK", "7610"]);
+    ///
+    /// assert_eq!(compare_records(&kenai, &selma, &keys), Ordering::Less);
+    /// assert_eq!(compare_records(&selma, &oakman, &keys), Ordering::Less);
+    /// // empty populations go last, even when sorting descending
+    /// assert_eq!(compare_records(&oakman, &sandfort, &keys), Ordering::Less);
+    /// ```
+    pub fn compare_records(a: &StringRecord, b: &StringRecord, keys: &[SortKey]) -> Ordering {
+        for key in keys {
+            let a = a.get(key.column).unwrap_or("");
+            let b = b.get(key.column).unwrap_or("");
+            let ordering = compare_cells(a, b, key.compare, key.ascending, &key.options);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Returns an estimate of the memory held by a record.
+    fn get_record_size(record: &StringRecord) -> usize {
+        size_of::<StringRecord>() + record.as_slice().len() + record.len() * size_of::<usize>()
+    }
+
+    /// Does a k-way merge of the sorted run files into `wtr`.
+    /// A heap holds the next record from each run, so the smallest remaining record is always
+    /// at the top.
+    fn merge_runs(
+        runs: &RunFiles,
+        wtr: &mut Writer<File>,
+        keys: &[SortKey],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut readers: Vec<Reader<File>> = Vec::new();
+        for path in runs.paths.iter() {
+            readers.push(
+                ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .from_path(path)?,
+            );
+        }
+
+        let mut heap: BinaryHeap<MergeEntry<'_>> = BinaryHeap::new();
+        for (run, reader) in readers.iter_mut().enumerate() {
+            let mut record = StringRecord::new();
+            if reader.read_record(&mut record)? {
+                heap.push(MergeEntry { record, run, keys });
+            }
+        }
+
+        while let Some(mut entry) = heap.pop() {
+            wtr.write_record(&entry.record)?;
+            // Refill the heap from the run the record came from
+            if readers[entry.run].read_record(&mut entry.record)? {
+                heap.push(entry);
+            }
+        }
+        wtr.flush()?;
         Ok(())
     }
 
-    /// Checks whether two string records match one another.  Intended for comparing a passed string record value
-    /// to the header, to avoid printing the header twice.
-    fn matches(record: &StringRecord, header: &StringRecord) -> bool {
-        if record == header {
-            return true;
-        };
-        false
+    /// The next record of a sorted run, waiting to be merged.
+    struct MergeEntry<'a> {
+        record: StringRecord,
+        run: usize,
+        keys: &'a [SortKey],
+    }
+
+    impl Ord for MergeEntry<'_> {
+        /// Reversed, as `BinaryHeap` pops the greatest entry first. Ties go to the earlier run,
+        /// which holds the records that came first in the file.
+        fn cmp(&self, other: &Self) -> Ordering {
+            compare_records(&self.record, &other.record, self.keys)
+                .then(self.run.cmp(&other.run))
+                .reverse()
+        }
+    }
+
+    impl PartialOrd for MergeEntry<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PartialEq for MergeEntry<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for MergeEntry<'_> {}
+
+    /// Temporary files holding the sorted runs of a sort. The files are removed when dropped.
+    struct RunFiles {
+        id: usize,
+        paths: Vec<PathBuf>,
+    }
+
+    impl RunFiles {
+        fn new() -> RunFiles {
+            RunFiles {
+                id: SORT_COUNT.fetch_add(1, AtomicOrdering::Relaxed),
+                paths: Vec::new(),
+            }
+        }
+
+        /// Writes an already sorted chunk of records to a new run file.
+        fn write_run(&mut self, chunk: &[StringRecord]) -> Result<(), Box<dyn Error>> {
+            let path = std::env::temp_dir().join(format!(
+                "csv_viewer_sort_{}_{}_{}.csv",
+                process::id(),
+                self.id,
+                self.paths.len()
+            ));
+            // Track the file before writing, so it is removed even if writing fails
+            self.paths.push(path.clone());
+            let mut wtr = WriterBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_path(path)?;
+            for record in chunk.iter() {
+                wtr.write_record(record)?;
+            }
+            wtr.flush()?;
+            Ok(())
+        }
+    }
+
+    impl Drop for RunFiles {
+        fn drop(&mut self) {
+            for path in self.paths.iter() {
+                if let Err(e) = remove_file(path) {
+                    eprintln!("Cannot remove sort run {}: {}", path.display(), e);
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,32 +1,71 @@
 pub mod viewer_app {
-    use std::borrow::{Borrow};
+    use csv::StringRecord;
 
-    use csv::{StringRecord};
-
-    use eframe::{Frame};
-    use egui::{Align2, Context, Pos2, Sense, Ui, Vec2};
+    use eframe::Frame;
+    use egui::text::{LayoutJob, TextFormat};
+    use egui::{Align2, Color32, Context, Pos2, Sense, Ui, Vec2};
     use egui_extras::{Column, TableBuilder};
     use egui_extras::{Size, StripBuilder};
 
     use atty;
     use rfd::FileDialog;
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::io;
+    use std::mem;
+    use std::ops::{Range, RangeInclusive};
 
-    use crate::find::find::{find_matching_rows, find_row_of_next};
+    use crate::cache::cache::RecordCache;
+    use crate::cli::cli::{spool_to_temp_file, Args};
+    use crate::clipboard::clipboard::{copy_records, parse_pasted, CopyFormat};
+    use crate::compare::compare::{CompareType, NullOrder};
+    use crate::edit::edit::{EditOverlay, RowId};
+    use crate::export::export::{
+        export_blocks, export_file, get_table_name, ExportFormat, ExportOptions, Quoting,
+        EXPORT_FORMATS,
+    };
+    use crate::filter::filter::RowFilter;
+    use crate::find::find::{
+        get_snippet, next_match, previous_match, BackgroundSearch, CellMatch, FindQuery,
+    };
+    use crate::history::history::{load_recipe, Edit, EditHistory};
+    use crate::index::index::{get_sidecar_path, RowIndex, DEFAULT_INDEX_STEP};
     use crate::reader::reader::*;
-    use crate::sort::sort::sort_records;
+    use crate::replace::replace::{preview_replacements, replace_to_file, CellChange};
+    use crate::sniff::sniff::sniff_file;
+    use crate::sort::sort::{sort_records, RowPermutation, SortKey};
+
+    /// Background colours of search matches highlighted in the table.
+    const MATCH_COLOR: Color32 = Color32::from_rgb(255, 230, 120);
+    const CURRENT_MATCH_COLOR: Color32 = Color32::from_rgb(255, 150, 50);
+    /// Maximum number of changed cells listed in the replace preview.
+    const REPLACE_PREVIEW_LIMIT: usize = 100;
 
     #[derive(PartialEq, Debug, Clone)]
     enum Delimiter {
         Comma,
         Tab,
         Semicolon,
+        Pipe,
+        Colon,
+        Auto,
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    enum HeaderRow {
+        Yes,
+        No,
         Auto,
     }
 
     struct FileInfo {
         delimiter: Delimiter,
+        header_row: HeaderRow,
+        // the dialect actually used to parse the open file
+        dialect: Dialect,
         // unsure about string slice atm
         file_size_mb: f64,
+        // number of data rows, not counting the header
         total_rows: usize,
         has_headers: bool,
     }
@@ -34,7 +73,9 @@ pub mod viewer_app {
     impl Default for FileInfo {
         fn default() -> Self {
             Self {
-                delimiter: Delimiter::Comma,
+                delimiter: Delimiter::Auto,
+                header_row: HeaderRow::Auto,
+                dialect: Dialect::default(),
                 file_size_mb: 0.0,
                 total_rows: 0,
                 has_headers: true,
@@ -56,21 +97,111 @@ pub mod viewer_app {
         StartOfFile,
         EndOfFile,
         ExportedFile,
+        ExportedView,
+        SavedFile,
+        NoMoreMatches,
+        ReplacedFile,
+    }
+
+    /// What to do once the user agrees to lose the unsaved changes to the open file.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    enum DiscardAction {
+        Open,
+        Close,
+    }
+
+    /// A rectangle of cells selected in the table, from the cell the selection started at to the
+    /// cell it was extended to. Cells are given as their position in the view and the index of
+    /// their column among the columns shown.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Selection {
+        anchor: (usize, usize),
+        cursor: (usize, usize),
+    }
+
+    impl Selection {
+        /// Returns the positions in the view of the selected rows.
+        fn rows(&self) -> RangeInclusive<usize> {
+            self.anchor.0.min(self.cursor.0)..=self.anchor.0.max(self.cursor.0)
+        }
+
+        /// Returns the indices among the shown columns of the selected columns.
+        fn columns(&self) -> RangeInclusive<usize> {
+            self.anchor.1.min(self.cursor.1)..=self.anchor.1.max(self.cursor.1)
+        }
+
+        fn contains(&self, pos: usize, column: usize) -> bool {
+            self.rows().contains(&pos) && self.columns().contains(&column)
+        }
     }
 
     pub struct AppSettings {
         has_file: bool,
+        // number of rows scrolled by Next Page and Previous Page
         num_rows_to_display: usize,
+        // position in the view of the top row shown in the table
         current_pos: usize,
         quit_confirmation: bool,
+        // waiting on the user to agree to lose the unsaved changes, to open or close a file
+        discard_confirmation: Option<DiscardAction>,
         allowed_to_quit: bool,
         dialog_open: bool,
         dialog_msg: DialogMessage,
-        index_selected_header: usize,
+        sort_keys: Vec<SortKey>,
+        // delimiter of the sorted file, `Auto` keeps the delimiter of the open file
+        sort_output_delimiter: Delimiter,
         show_finder: bool,
-        find_string: String,
+        find_query: FindQuery,
+        // why the last search failed, e.g. an invalid regular expression
+        find_error: Option<String>,
+        // the search still running in the background, if any
+        find_search: Option<BackgroundSearch>,
+        find_matching_cells: Vec<CellMatch>,
+        // rows holding at least one of the matching cells, in file order
         find_matching_rows: Vec<usize>,
-        find_matches_index: usize,
+        // index within `find_matching_cells` of the match last navigated to
+        find_current_match: Option<usize>,
+        // position in the view to scroll to on the next frame, and where to align it
+        scroll_to: Option<(usize, egui::Align)>,
+        // whether find next and previous continue from the other end of the matches
+        find_wrap: bool,
+        show_find_results: bool,
+        // snippets of the matches listed in the results panel, keyed by match index
+        find_snippets: HashMap<usize, String>,
+        filter_text: String,
+        // why the last filter could not be applied, e.g. an unknown column
+        filter_error: Option<String>,
+        show_replace: bool,
+        replace_with: String,
+        replace_preview: Vec<CellChange>,
+        show_goto: bool,
+        goto_text: String,
+        // columns of the file not shown in the table, nor exported
+        hidden_columns: HashSet<usize>,
+        show_csv_export: bool,
+        // delimiter of the exported CSV file, `Auto` keeps the delimiter of the open file
+        csv_export_delimiter: Delimiter,
+        csv_export_quoting: Quoting,
+        csv_export_terminator: LineEnding,
+        csv_export_bom: bool,
+        // row of the edited file and column of the cell being edited, and its text
+        editing_cell: Option<(usize, usize)>,
+        edit_text: String,
+        // set when editing starts, so the text box takes the keyboard focus once
+        edit_focus: bool,
+        // column being renamed, and its new name
+        rename_column: Option<usize>,
+        rename_text: String,
+        show_history: bool,
+        // whether the number typed into Go To Line is a physical line rather than a row number
+        goto_physical_line: bool,
+        goto_error: Option<String>,
+        // file row last gone to with Go To Line, highlighted in the table
+        goto_row: Option<usize>,
+        // cells selected in the table to copy, or to paste over from their top left cell
+        selection: Option<Selection>,
+        // whether the selection is being extended by dragging over the table
+        drag_selecting: bool,
     }
 
     impl Default for AppSettings {
@@ -80,14 +211,47 @@ pub mod viewer_app {
                 num_rows_to_display: 100,
                 current_pos: 0,
                 quit_confirmation: false,
+                discard_confirmation: None,
                 allowed_to_quit: false,
                 dialog_open: false,
                 dialog_msg: DialogMessage::None,
-                index_selected_header: 0,
+                sort_keys: Vec::new(),
+                sort_output_delimiter: Delimiter::Auto,
                 show_finder: false,
-                find_string: String::from(""),
+                find_query: FindQuery::default(),
+                find_error: None,
+                find_search: None,
+                find_matching_cells: Vec::new(),
                 find_matching_rows: Vec::new(),
-                find_matches_index: 0,
+                find_current_match: None,
+                scroll_to: None,
+                find_wrap: true,
+                show_find_results: false,
+                find_snippets: HashMap::new(),
+                filter_text: String::from(""),
+                filter_error: None,
+                show_replace: false,
+                replace_with: String::from(""),
+                replace_preview: Vec::new(),
+                show_goto: false,
+                goto_text: String::from(""),
+                hidden_columns: HashSet::new(),
+                show_csv_export: false,
+                csv_export_delimiter: Delimiter::Auto,
+                csv_export_quoting: Quoting::Necessary,
+                csv_export_terminator: LineEnding::Lf,
+                csv_export_bom: false,
+                editing_cell: None,
+                edit_text: String::from(""),
+                edit_focus: false,
+                rename_column: None,
+                rename_text: String::from(""),
+                show_history: false,
+                goto_physical_line: false,
+                goto_error: None,
+                goto_row: None,
+                selection: None,
+                drag_selecting: false,
             }
         }
     }
@@ -96,8 +260,22 @@ pub mod viewer_app {
         app_state: AppState,
         file_info: FileInfo,
         headers: StringRecord,
-        records: Vec<StringRecord>,
+        // blocks of rows read around those scrolled to, by their position in the view
+        records: RecordCache,
         file_path: Option<String>,
+        row_index: Option<RowIndex>,
+        // order the rows are viewed in when the view is sorted, the file itself is left as it is
+        view_order: Option<RowPermutation>,
+        // rows matching the filter, when only rows matching a filter are viewed
+        view_filter: Option<RowFilter>,
+        // the rows shown, in order, when the view is both sorted and filtered
+        view_rows: Option<Vec<usize>>,
+        // cells, rows and columns changed in the viewer and not yet saved
+        edits: EditOverlay,
+        // the changes made to the edits, to undo and redo them
+        history: EditHistory,
+        // temporary file holding the data piped in on stdin, removed when the app exits
+        spooled_file: Option<String>,
         settings: AppSettings,
     }
 
@@ -108,8 +286,15 @@ pub mod viewer_app {
                 app_state: AppState::MainMenu,
                 file_info: FileInfo::default(),
                 headers: Default::default(),
-                records: Vec::new(),
+                records: RecordCache::default(),
                 file_path: None,
+                row_index: None,
+                view_order: None,
+                view_filter: None,
+                view_rows: None,
+                edits: EditOverlay::default(),
+                history: EditHistory::default(),
+                spooled_file: None,
                 settings: Default::default(),
             }
         }
@@ -125,20 +310,56 @@ pub mod viewer_app {
                 }
                 AppState::Viewer => {
                     show_viewer_window(self, ctx);
+                    // leave the shortcuts to a text box being typed in
+                    if !ctx.wants_keyboard_input() {
+                        let events = ctx.input(|i| i.events.clone());
+                        for event in events {
+                            match event {
+                                egui::Event::Copy => copy_selection(self, ctx, CopyFormat::Tsv),
+                                egui::Event::Paste(text) => paste_text(self, &text),
+                                _ => {}
+                            }
+                        }
+                        if ctx.input_mut(|i| {
+                            i.consume_key(
+                                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                                egui::Key::Z,
+                            )
+                        }) {
+                            redo_edit(self);
+                        } else if ctx
+                            .input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z))
+                        {
+                            undo_edit(self);
+                        }
+                    }
                 }
                 AppState::Sorter => {
                     show_sorter_window(self, ctx, frame);
                 }
             }
 
+            poll_find_search(self, ctx);
             if self.settings.show_finder == true {
                 show_find_window(self, ctx, frame);
             }
+            if self.settings.show_goto {
+                show_goto_window(self, ctx);
+            }
+            if self.settings.show_csv_export {
+                show_csv_export_window(self, ctx);
+            }
+            if self.settings.rename_column.is_some() {
+                show_rename_column_window(self, ctx);
+            }
 
             // If the quit confirmation setting is enabled, open the quit confirmation menu.
             if self.settings.quit_confirmation {
                 show_quit_confirmation(self, ctx, frame);
             }
+            if let Some(action) = self.settings.discard_confirmation {
+                show_discard_confirmation(self, ctx, action);
+            }
 
             // Checks if a dialog box with a required confirmation to close is set to open
             if self.settings.dialog_open == true {
@@ -160,6 +381,12 @@ pub mod viewer_app {
                         dialog_msg = "Already at End of File";
                     }
                     DialogMessage::ExportedFile => dialog_msg = "Sorted File Exported Successfully",
+                    DialogMessage::ExportedView => dialog_msg = "File Exported Successfully",
+                    DialogMessage::SavedFile => dialog_msg = "File Saved Successfully",
+                    DialogMessage::NoMoreMatches => dialog_msg = "No More Matches",
+                    DialogMessage::ReplacedFile => {
+                        dialog_msg = "Replaced File Exported Successfully"
+                    }
                 }
                 show_dialog_confirmation(self, ctx, dialog_msg);
             }
@@ -172,6 +399,14 @@ pub mod viewer_app {
             self.settings.allowed_to_quit
         }
 
+        /// Removes the file spooled from stdin, and the row index built for it.
+        fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+            if let Some(path) = self.spooled_file.take() {
+                let _ = fs::remove_file(get_sidecar_path(&path));
+                let _ = fs::remove_file(path);
+            }
+        }
+
         // save current state before shutdown. EXPERIMENTAL ATM
         // fn save(&mut self, storage: &mut dyn eframe::Storage) {
         //     eframe::set_value(storage, eframe::APP_KEY, self);
@@ -189,10 +424,28 @@ pub mod viewer_app {
         if d == Delimiter::Semicolon {
             delim = ';';
         }
+        if d == Delimiter::Pipe {
+            delim = '|';
+        }
+        if d == Delimiter::Colon {
+            delim = ':';
+        }
 
         return delim;
     }
 
+    /// Returns the delimiter setting for `delimiter`, or `Auto` if it is not one of the settings.
+    fn get_delimiter_setting(delimiter: char) -> Delimiter {
+        match delimiter {
+            ',' => Delimiter::Comma,
+            '\t' => Delimiter::Tab,
+            ';' => Delimiter::Semicolon,
+            '|' => Delimiter::Pipe,
+            ':' => Delimiter::Colon,
+            _ => Delimiter::Auto,
+        }
+    }
+
     /// Shows the main menu window inside the frame.
     fn show_main_menu_window(app: &mut ViewerApp, ctx: &Context, frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -206,19 +459,25 @@ pub mod viewer_app {
                     ui.add(
                         egui::Slider::new(&mut app.settings.num_rows_to_display, 10..=1000)
                             .logarithmic(true)
-                            .text("Max Rows to Display"),
+                            .text("Rows per Page"),
                     );
                     ui.label(format!(
                         "Has Headers: {:?}",
-                        app.file_info.has_headers.borrow()
+                        app.file_info.header_row.clone()
                     ));
                     ui.horizontal(|ui| {
                         if ui
-                            .radio_value(&mut app.file_info.has_headers, true, "Yes")
+                            .radio_value(&mut app.file_info.header_row, HeaderRow::Yes, "Yes")
+                            .clicked()
+                        {}
+                        if ui
+                            .radio_value(&mut app.file_info.header_row, HeaderRow::No, "No")
+                            .clicked()
+                        {}
+                        if ui
+                            .radio_value(&mut app.file_info.header_row, HeaderRow::Auto, "AUTO")
                             .clicked()
                         {}
-                        // if ui.radio_value(&mut app.file_info.has_headers,
-                        //                   false, "No").clicked() {}
                     });
                     ui.label(format!(
                         "Delimiter Character: {:?}",
@@ -241,8 +500,18 @@ pub mod viewer_app {
                             )
                             .clicked()
                         {}
-                        // if ui.radio_value(&mut app.file_info.delimiter,
-                        //                   Delimiter::Auto, "AUTO").clicked() {}
+                        if ui
+                            .radio_value(&mut app.file_info.delimiter, Delimiter::Pipe, "PIPE")
+                            .clicked()
+                        {}
+                        if ui
+                            .radio_value(&mut app.file_info.delimiter, Delimiter::Colon, "COLON")
+                            .clicked()
+                        {}
+                        if ui
+                            .radio_value(&mut app.file_info.delimiter, Delimiter::Auto, "AUTO")
+                            .clicked()
+                        {}
                     });
                     ui.separator();
                     ui.horizontal(|ui| {
@@ -268,20 +537,47 @@ pub mod viewer_app {
                 ui.menu_button("File", |ui| {
                     // Opens file dialogue window.
                     if ui.button("Open").clicked() {
-                        open_file(app);
+                        discard_edits_then(app, DiscardAction::Open);
+                    }
+                    // Piped data is spooled to a temporary file, removed on exit, so it can
+                    // only be saved somewhere else.
+                    let is_spooled =
+                        app.spooled_file.is_some() && app.file_path == app.spooled_file;
+                    if ui
+                        .add_enabled(
+                            app.edits.is_modified() && !is_spooled,
+                            egui::Button::new("Save"),
+                        )
+                        .on_disabled_hover_text(if is_spooled {
+                            "Piped data has no file to save to, use Save As..."
+                        } else {
+                            "There are no changes to save"
+                        })
+                        .clicked()
+                    {
+                        save_file(app, app.file_path.clone().unwrap());
                     }
-                    // Export Changes to file
-                    if ui.button("(TBA)Export to...").clicked() {
-                        // code here
+                    if ui.button("Save As...").clicked() {
+                        if let Some(path) = FileDialog::new().save_file() {
+                            save_file(app, path.display().to_string());
+                        }
                     }
+                    // Export the rows and columns shown to another format
+                    ui.menu_button("Export to", |ui| {
+                        for format in EXPORT_FORMATS {
+                            if ui.button(format!("{}...", format.name())).clicked() {
+                                ui.close_menu();
+                                if format == ExportFormat::Csv {
+                                    app.settings.show_csv_export = true;
+                                } else {
+                                    export_view(app, format);
+                                }
+                            }
+                        }
+                    });
                     // Closes the opened file and returns to main menu.
                     if ui.button("Close").clicked() {
-                        app.headers = StringRecord::new();
-                        app.records = Vec::new();
-                        app.file_path = None;
-                        app.file_info = FileInfo::default();
-                        app.settings = AppSettings::default();
-                        app.app_state = AppState::MainMenu;
+                        discard_edits_then(app, DiscardAction::Close);
                     }
                     if ui.button("Quit").clicked() {
                         // Quit Confirmation Dialogue
@@ -290,27 +586,90 @@ pub mod viewer_app {
                 });
                 // Opens the Edit menu from the top bar
                 ui.menu_button("Edit", |ui| {
-                    if ui.button("(TBA)Copy").clicked() {
-                        // code here
+                    if ui
+                        .add_enabled(app.history.can_undo(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        undo_edit(app);
                     }
-                    if ui.button("(TBA)Paste").clicked() {
-                        // code here
+                    if ui
+                        .add_enabled(app.history.can_redo(), egui::Button::new("Redo"))
+                        .clicked()
+                    {
+                        redo_edit(app);
+                    }
+                    if ui.button("History").clicked() {
+                        app.settings.show_history = !app.settings.show_history;
+                    }
+                    if ui
+                        .add_enabled(app.history.can_undo(), egui::Button::new("Save Recipe..."))
+                        .clicked()
+                    {
+                        if let Some(path) = FileDialog::new().save_file() {
+                            if let Err(e) = app.history.save_recipe(&path.display().to_string()) {
+                                println!("Error: Cannot Save Recipe: {}", e);
+                            }
+                        }
+                    }
+                    if ui.button("Apply Recipe...").clicked() {
+                        apply_recipe(app);
+                    }
+                    ui.separator();
+                    let selected = app.settings.selection.is_some();
+                    for (label, format) in [
+                        ("Copy", CopyFormat::Tsv),
+                        ("Copy as CSV", CopyFormat::Csv),
+                        ("Copy as Markdown", CopyFormat::Markdown),
+                    ] {
+                        if ui.add_enabled(selected, egui::Button::new(label)).clicked() {
+                            copy_selection(app, ctx, format);
+                            ui.close_menu();
+                        }
                     }
+                    // the clipboard is only read by egui when it is pasted into the window
+                    ui.add_enabled(false, egui::Button::new("Paste"))
+                        .on_disabled_hover_text("Press Ctrl+V over the table to paste");
                 });
                 // Opens the Data menu from the top bar
                 ui.menu_button("Data", |ui| {
-                    if ui.button("(WIP)Sort...").clicked() {
+                    if ui
+                        .add_enabled(!app.edits.is_modified(), egui::Button::new("Sort..."))
+                        .on_disabled_hover_text("Save the edits first")
+                        .clicked()
+                    {
                         app.app_state = AppState::Sorter;
                     }
+                    if ui
+                        .add_enabled(app.view_order.is_some(), egui::Button::new("Unsort View"))
+                        .clicked()
+                    {
+                        clear_view_sort(app);
+                    }
+                    ui.menu_button("Columns", |ui| {
+                        for (column, header) in app.headers.iter().enumerate() {
+                            let mut shown = !app.settings.hidden_columns.contains(&column);
+                            if ui.checkbox(&mut shown, header).changed() {
+                                if shown {
+                                    app.settings.hidden_columns.remove(&column);
+                                } else {
+                                    app.settings.hidden_columns.insert(column);
+                                }
+                                app.settings.selection = None;
+                            }
+                        }
+                    });
                 });
                 // Opens the Find menu from the top bar
                 ui.menu_button("Navigate", |ui| {
-                    if ui.button("(TBA) Go To Line...").clicked() {
-                        // code here
+                    if ui.button("Go To Line...").clicked() {
+                        app.settings.show_goto = true;
                     }
                     if ui.button("Find...").clicked() {
                         app.settings.show_finder = true;
                     }
+                    if ui.button("Find Results").clicked() {
+                        app.settings.show_find_results = !app.settings.show_find_results;
+                    }
                     if ui.button("Go To Start of File").clicked() {
                         show_first_page(app);
                     }
@@ -327,6 +686,14 @@ pub mod viewer_app {
             });
         });
 
+        show_filter_panel(app, ctx);
+        if app.settings.show_find_results {
+            show_find_results_panel(app, ctx);
+        }
+        if app.settings.show_history {
+            show_history_panel(app, ctx);
+        }
+
         // Central Panel. Displays the Cells.
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::ScrollArea::horizontal()
@@ -352,20 +719,31 @@ pub mod viewer_app {
                 .show(ctx, |ui| {
                     ui.horizontal_centered(|ui| {
                         // Display the total record count of file and page's position in file
-                        if app.file_info.has_headers && (app.file_info.total_rows > 1) {
-                            // If a file had headers, remove header from row count
+                        match &app.view_filter {
+                            Some(filter) => ui.label(format!(
+                                "Rows: {} of {}",
+                                filter.len(),
+                                app.file_info.total_rows
+                            )),
+                            None => ui.label(format!("Total Rows: {}", app.edits.len())),
+                        };
+                        if app.edits.is_modified() {
+                            ui.label("Modified");
+                        }
+                        ui.label(format!("Top Pos: {}", app.settings.current_pos.clone() + 1));
+                        ui.label(format!(
+                            "Delimiter: {:?}",
+                            app.file_info.dialect.delimiter as char
+                        ));
+                        if let Some(order) = &app.view_order {
+                            ui.label(format!("Sorted By: {}", get_sort_description(app, order)));
+                        }
+                        if let Some(i) = app.settings.find_current_match {
                             ui.label(format!(
-                                "Total Rows: {}",
-                                app.file_info.total_rows.clone() - 1
+                                "Match {} of {}",
+                                i + 1,
+                                app.settings.find_matching_cells.len()
                             ));
-                        } else {
-                            ui.label(format!("Total Rows: {}", app.file_info.total_rows.clone()));
-                        }
-                        if app.file_info.has_headers && (app.settings.current_pos > 0) {
-                            // ignore header row in count.
-                            ui.label(format!("Top Pos: {}", app.settings.current_pos.clone() + 1));
-                        } else {
-                            ui.label(format!("Top Pos: {}", app.settings.current_pos.clone()));
                         }
                         if ui.button("Next Page").clicked() {
                             show_next_page(app);
@@ -380,14 +758,47 @@ pub mod viewer_app {
         });
     }
 
+    /// Builds the table of the rows in the view. Only the rows scrolled into view are laid out,
+    /// and they are read from the file in blocks kept in the record cache.
+    /// Clicking a column header sorts the view by that column, and clicking it again reverses the
+    /// direction. Double-clicking a cell edits it, and right-clicking a row number or column
+    /// header opens a menu to insert or delete rows and columns.
     fn build_table(app: &mut ViewerApp, ctx: &Context, ui: &mut Ui) {
-        TableBuilder::new(ui)
+        let mut clicked_column: Option<usize> = None;
+        let mut edit_action: Option<EditAction> = None;
+        // a cell double-clicked to start editing it, and whether editing finished and was kept
+        let mut start_edit: Option<(usize, usize, String)> = None;
+        let mut finish_edit: Option<bool> = None;
+        let mut edit_text = mem::take(&mut app.settings.edit_text);
+        let focus_edit = mem::take(&mut app.settings.edit_focus);
+        // the corners of cells clicked to select them, and the cell dragged over to extend to
+        let mut select: Option<((usize, usize), (usize, usize))> = None;
+        let mut drag_to: Option<(usize, usize)> = None;
+        let mut drag_selecting =
+            app.settings.drag_selecting && ctx.input(|i| i.pointer.primary_down());
+        let mut table = TableBuilder::new(ui)
             .max_scroll_height(f32::INFINITY)
             .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
             .striped(true) // Eventually needs to be a struct parameter
             .resizable(true) // Eventually needs to be a struct parameter
-            .columns(Column::auto().resizable(true), app.headers.len())
-            .column(Column::remainder())
+            .columns(Column::auto().resizable(true), get_shown_columns(app).len())
+            .column(Column::remainder());
+        if let Some((pos, align)) = app.settings.scroll_to.take() {
+            table = table.scroll_to_row(pos, Some(align));
+        }
+        // the cache is filled while the rows are laid out, which only needs to read the app
+        let mut records = mem::take(&mut app.records);
+        let mut top_pos: Option<usize> = None;
+        let view: &ViewerApp = app;
+        let shown_columns = get_shown_columns(view);
+        let view_len = get_view_len(view);
+        let last_column = shown_columns.len().saturating_sub(1);
+        let selection = view.settings.selection;
+        let current_match = view
+            .settings
+            .find_current_match
+            .and_then(|i| view.settings.find_matching_cells.get(i));
+        table
             .header(20.0, |mut header| {
                 header.col(|ui| {
                     if ui
@@ -403,210 +814,1229 @@ pub mod viewer_app {
                             });
                     }
                 });
-                for record in app.headers.iter() {
+                for (shown, column) in shown_columns.iter().copied().enumerate() {
+                    let record = view.headers.get(column).unwrap_or("");
                     header.col(|ui| {
+                        // mark the column the view is sorted by with its direction
+                        let sort_label = match &view.view_order {
+                            Some(order) if order.keys[0].column == column => {
+                                if order.keys[0].ascending {
+                                    "⏶"
+                                } else {
+                                    "⏷"
+                                }
+                            }
+                            _ => "↕",
+                        };
+                        // the view is sorted by the file on disk, which does not hold the edits
                         if ui
-                            .add(egui::Label::new(format!("{}", record)).sense(Sense::click()))
+                            .add_enabled(
+                                !view.edits.is_modified(),
+                                egui::Button::new(sort_label).small(),
+                            )
+                            .on_hover_text("Sort")
+                            .on_disabled_hover_text("Save the edits first")
                             .clicked()
                         {
-                            egui::Window::new("Clicked header")
-                                .anchor(Align2::CENTER_CENTER, (Vec2 { x: 0.0, y: 0.0 }))
-                                .collapsible(false)
-                                .resizable(false)
-                                .show(ctx, |ui| {
-                                    ui.label(format!("Clicked Header: {}", record));
-                                });
+                            clicked_column = Some(column);
+                        }
+                        let response = ui.add(egui::Label::new(record).sense(Sense::click()));
+                        if response.clicked() {
+                            select = Some(((0, shown), (view_len.saturating_sub(1), shown)));
                         };
+                        response.context_menu(|ui| {
+                            show_column_menu(view, ui, column, &mut edit_action);
+                        });
                     });
                 }
             })
-            .body(|mut body| {
-                for (line, record) in app.records.iter().enumerate() {
-                    body.row(30.0, |mut row| {
-                        // display row index
-                        row.col(|ui| {
-                            ui.label(format!("{}", app.settings.current_pos.clone() + line + 1));
-                        });
-                        for column in record {
-                            row.col(|ui| {
-                                ui.label(format!("{}", column));
-                            });
+            .body(|body| {
+                body.rows(30.0, view_len, |pos, mut row| {
+                    top_pos = Some(top_pos.map_or(pos, |top| top.min(pos)));
+                    // a sorted view shows the row's number within the file
+                    let row_number = get_row_number(view, pos);
+                    // matches are found in the file on disk, so are looked up by its row numbers
+                    let row_matches = match view.edits.row_id(row_number) {
+                        Some(RowId::File(file_row)) => {
+                            get_row_matches(&view.settings.find_matching_cells, file_row)
+                        }
+                        _ => &[],
+                    };
+                    let highlighted = view.settings.goto_row == Some(row_number);
+                    // display row index
+                    row.col(|ui| {
+                        if highlighted {
+                            paint_row_highlight(ui);
+                        }
+                        let response = ui.add(
+                            egui::Label::new(format!("{}", row_number + 1)).sense(Sense::click()),
+                        );
+                        if response.clicked() {
+                            select = Some(((pos, 0), (pos, last_column)));
                         }
+                        response.context_menu(|ui| {
+                            show_row_menu(view, ui, row_number, &mut edit_action);
+                        });
                     });
+                    let record = match records.get(pos, |start, n| get_rows(view, start, n)) {
+                        Some(record) => record,
+                        None => return,
+                    };
+                    for (shown, column) in shown_columns.iter().copied().enumerate() {
+                        let cell = record.get(column).unwrap_or("");
+                        row.col(|ui| {
+                            if highlighted {
+                                paint_row_highlight(ui);
+                            }
+                            if selection.map_or(false, |selected| selected.contains(pos, shown)) {
+                                paint_selection(ui);
+                            }
+                            if view.settings.editing_cell == Some((row_number, column)) {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut edit_text)
+                                        .desired_width(f32::INFINITY),
+                                );
+                                if focus_edit {
+                                    response.request_focus();
+                                }
+                                if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                                    finish_edit = Some(false);
+                                } else if response.lost_focus() {
+                                    finish_edit = Some(true);
+                                }
+                                return;
+                            }
+                            // an edited cell no longer holds the text the matches were found in
+                            let file_column = view
+                                .edits
+                                .file_column(column)
+                                .filter(|_| !view.edits.is_cell_edited(row_number, column));
+                            let cell_matches: Vec<&CellMatch> = row_matches
+                                .iter()
+                                .filter(|found| Some(found.column) == file_column)
+                                .collect();
+                            let label = if cell_matches.is_empty() {
+                                egui::Label::new(cell)
+                            } else {
+                                egui::Label::new(highlight_cell(
+                                    ui,
+                                    cell,
+                                    &cell_matches,
+                                    current_match,
+                                ))
+                            };
+                            let response = ui.add(label.sense(Sense::click_and_drag()));
+                            if response.double_clicked() {
+                                start_edit = Some((row_number, column, cell.to_string()));
+                            } else if response.clicked() || response.drag_started() {
+                                select = Some(((pos, shown), (pos, shown)));
+                                drag_selecting = response.drag_started();
+                            } else if drag_selecting && ui.rect_contains_pointer(ui.max_rect()) {
+                                drag_to = Some((pos, shown));
+                            }
+                        });
+                    }
+                });
+            });
+        app.records = records;
+        app.settings.edit_text = edit_text;
+        app.settings.drag_selecting = drag_selecting;
+        if let Some((anchor, cursor)) = select {
+            // shift-clicking extends the selection to the cells clicked instead of starting another
+            app.settings.selection = match app.settings.selection {
+                Some(selected) if ctx.input(|i| i.modifiers.shift) => {
+                    Some(Selection { cursor, ..selected })
+                }
+                _ => Some(Selection { anchor, cursor }),
+            };
+        }
+        if let (Some(cursor), Some(selected)) = (drag_to, app.settings.selection.as_mut()) {
+            selected.cursor = cursor;
+        }
+        if let Some(pos) = top_pos {
+            app.settings.current_pos = pos;
+        }
+
+        if let Some(keep) = finish_edit {
+            if let Some((row, column)) = app.settings.editing_cell.take() {
+                let text = mem::take(&mut app.settings.edit_text);
+                if keep {
+                    record_edit(
+                        app,
+                        Edit::SetCell {
+                            row,
+                            column,
+                            value: text,
+                        },
+                    );
+                }
+            }
+        }
+        if let Some((row, column, text)) = start_edit {
+            app.settings.editing_cell = Some((row, column));
+            app.settings.edit_text = text;
+            app.settings.edit_focus = true;
+        }
+        if let Some(action) = edit_action {
+            apply_edit_action(app, action);
+        }
+
+        if let Some(column) = clicked_column {
+            let keys = match &app.view_order {
+                Some(order) if order.keys.len() == 1 && order.keys[0].column == column => {
+                    vec![SortKey {
+                        ascending: !order.keys[0].ascending,
+                        ..order.keys[0].clone()
+                    }]
                 }
+                _ => vec![SortKey::new(column)],
+            };
+            sort_view(app, keys);
+        }
+    }
+
+    /// Returns the file rows shown at `pos` and up to `n` rows after it in a sorted or filtered
+    /// view, or `None` when the view shows every row of the file in order.
+    fn get_view_range(app: &ViewerApp, pos: usize, n: usize) -> Option<&[usize]> {
+        if let Some(rows) = &app.view_rows {
+            let start = pos.min(rows.len());
+            let end = pos.saturating_add(n).min(rows.len());
+            return Some(&rows[start..end]);
+        }
+        if let Some(order) = &app.view_order {
+            return Some(order.get_range(pos, n));
+        }
+        if let Some(filter) = &app.view_filter {
+            return Some(filter.get_range(pos, n));
+        }
+        None
+    }
+
+    /// Returns the number of rows in the view, which is fewer than the file when filtered.
+    fn get_view_len(app: &ViewerApp) -> usize {
+        match (&app.view_rows, &app.view_filter) {
+            (Some(rows), _) => rows.len(),
+            (None, Some(filter)) => filter.len(),
+            (None, None) => app.edits.len(),
+        }
+    }
+
+    /// Returns the position in the view of file row `row`, or `None` if it is filtered out.
+    fn get_view_position(app: &ViewerApp, row: usize) -> Option<usize> {
+        if let Some(rows) = &app.view_rows {
+            return rows.iter().position(|r| *r == row);
+        }
+        if let Some(order) = &app.view_order {
+            return order.position_of(row);
+        }
+        if let Some(filter) = &app.view_filter {
+            return filter.position_of(row);
+        }
+        Some(row)
+    }
+
+    /// Returns the number within the file of the data row shown at `pos` of the view.
+    fn get_row_number(app: &ViewerApp, pos: usize) -> usize {
+        match get_view_range(app, pos, 1) {
+            Some(rows) => rows.first().copied().unwrap_or(pos),
+            None => pos,
+        }
+    }
+
+    /// Combines the sort order and filter of the view, when both are set, into the list of rows
+    /// shown. Must be called whenever either changes.
+    fn update_view_rows(app: &mut ViewerApp) {
+        app.view_rows = match (&app.view_order, &app.view_filter) {
+            (Some(order), Some(filter)) => Some(
+                order
+                    .get_range(0, order.len())
+                    .iter()
+                    .filter(|row| filter.contains(**row))
+                    .copied()
+                    .collect(),
+            ),
+            _ => None,
+        };
+    }
+
+    /// Opens the CSV export window, where the delimiter, quoting, line endings and byte order mark
+    /// of the exported file are chosen, e.g. to convert a semicolon delimited file to commas.
+    fn show_csv_export_window(app: &mut ViewerApp, ctx: &Context) {
+        egui::Window::new("Export as CSV")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2 { x: 0.0, y: 0.0 })
+            .show(ctx, |ui| {
+                ui.label("Output Delimiter:");
+                ui.horizontal(|ui| {
+                    let output_delimiter = &mut app.settings.csv_export_delimiter;
+                    ui.radio_value(output_delimiter, Delimiter::Auto, "SAME");
+                    ui.radio_value(output_delimiter, Delimiter::Comma, "COMMA");
+                    ui.radio_value(output_delimiter, Delimiter::Tab, "TAB");
+                    ui.radio_value(output_delimiter, Delimiter::Semicolon, "SEMICOLON");
+                    ui.radio_value(output_delimiter, Delimiter::Pipe, "PIPE");
+                    ui.radio_value(output_delimiter, Delimiter::Colon, "COLON");
+                });
+                ui.label("Quote Fields:");
+                ui.horizontal(|ui| {
+                    let quoting = &mut app.settings.csv_export_quoting;
+                    ui.radio_value(quoting, Quoting::Necessary, "When Necessary");
+                    ui.radio_value(quoting, Quoting::Always, "Always");
+                    ui.radio_value(quoting, Quoting::NonNumeric, "Non-Numeric");
+                    ui.radio_value(quoting, Quoting::Never, "Never");
+                });
+                ui.label("Line Endings:");
+                ui.horizontal(|ui| {
+                    let terminator = &mut app.settings.csv_export_terminator;
+                    ui.radio_value(terminator, LineEnding::Lf, "LF");
+                    ui.radio_value(terminator, LineEnding::Crlf, "CRLF");
+                });
+                ui.checkbox(&mut app.settings.csv_export_bom, "Write UTF-8 BOM");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Export as...").clicked() {
+                        app.settings.show_csv_export = false;
+                        export_view(app, ExportFormat::Csv);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        app.settings.show_csv_export = false;
+                    }
+                });
             });
     }
 
-    /// Updates the records field of the `ViewerApp` with the next page's records.
-    fn show_next_page(app: &mut ViewerApp) {
-        if app.settings.current_pos + app.settings.num_rows_to_display <= app.file_info.total_rows {
-            app.records = get_records_from_pos(
-                app.file_path.clone(),
-                app.settings.current_pos.clone() + app.settings.num_rows_to_display,
-                app.settings.num_rows_to_display.clone(),
-                app.file_info.has_headers,
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
-            app.settings.current_pos = app.settings.current_pos + app.settings.num_rows_to_display;
-            if (app.settings.current_pos + app.settings.num_rows_to_display)
-                > app.file_info.total_rows
-            {}
-        } else
-        /* No more content in file */
+    /// A change to the rows or columns of the open file, chosen from a menu of the table.
+    enum EditAction {
+        InsertRow(usize),
+        DeleteRow(usize),
+        InsertColumn(usize),
+        DeleteColumn(usize),
+        RenameColumn(usize),
+    }
+
+    /// Returns true if the view shows every row of the file in order, which is needed to insert or
+    /// delete rows and columns.
+    fn is_view_unchanged(app: &ViewerApp) -> bool {
+        app.view_order.is_none() && app.view_filter.is_none()
+    }
+
+    /// Shows the menu of a row number, to insert a row next to the row or delete it.
+    fn show_row_menu(app: &ViewerApp, ui: &mut Ui, row: usize, action: &mut Option<EditAction>) {
+        let enabled = is_view_unchanged(app);
+        if ui
+            .add_enabled(enabled, egui::Button::new("Insert Row Above"))
+            .clicked()
         {
-            app.settings.dialog_msg = DialogMessage::NextPage;
-            app.settings.dialog_open = true;
+            *action = Some(EditAction::InsertRow(row));
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(enabled, egui::Button::new("Insert Row Below"))
+            .clicked()
+        {
+            *action = Some(EditAction::InsertRow(row + 1));
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(enabled, egui::Button::new("Delete Row"))
+            .clicked()
+        {
+            *action = Some(EditAction::DeleteRow(row));
+            ui.close_menu();
         }
     }
 
-    /// Updates the records field of the `ViewerApp` with the previous page's records.
-    /// The number of records displayed on the page, depends on the value ViewerApp's `num_rows_to_display`
-    /// field.
-    /// If there aren't enough records on the previous page or the current row position is 0
-    /// the `current_pos` field is set to 0;
-    fn show_prev_page(app: &mut ViewerApp) {
-        // Shows previous page
-        // go back NUM_ROWS_TO_DISPLAY,
-        // unless ( pos - display ) < 0
-        if app.settings.current_pos <= app.settings.num_rows_to_display {
-            app.settings.current_pos = 0;
-            app.records = get_records_from_pos(
-                app.file_path.clone(),
-                app.settings.current_pos.clone(),
-                app.settings.num_rows_to_display.clone(),
-                app.file_info.has_headers,
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
-        } else {
-            app.settings.current_pos = app.settings.current_pos - app.settings.num_rows_to_display;
-            app.records = get_records_from_pos(
-                app.file_path.clone(),
-                app.settings.current_pos.clone(),
-                app.settings.num_rows_to_display.clone(),
+    /// Shows the menu of a column header, to insert, delete or rename columns.
+    fn show_column_menu(
+        app: &ViewerApp,
+        ui: &mut Ui,
+        column: usize,
+        action: &mut Option<EditAction>,
+    ) {
+        let enabled = is_view_unchanged(app);
+        if ui
+            .add_enabled(enabled, egui::Button::new("Insert Column Left"))
+            .clicked()
+        {
+            *action = Some(EditAction::InsertColumn(column));
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(enabled, egui::Button::new("Insert Column Right"))
+            .clicked()
+        {
+            *action = Some(EditAction::InsertColumn(column + 1));
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(enabled, egui::Button::new("Delete Column"))
+            .clicked()
+        {
+            *action = Some(EditAction::DeleteColumn(column));
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(
                 app.file_info.has_headers,
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
+                egui::Button::new("Rename Column..."),
+            )
+            .on_disabled_hover_text("The file has no header row to keep the name in")
+            .clicked()
+        {
+            *action = Some(EditAction::RenameColumn(column));
+            ui.close_menu();
         }
     }
 
-    fn show_first_page(app: &mut ViewerApp) {
-        if app.settings.current_pos != 0 {
-            app.settings.current_pos = 0;
-            app.records = get_records_from_pos(
-                app.file_path.clone(),
-                app.settings.current_pos.clone(),
-                app.settings.num_rows_to_display.clone(),
-                app.file_info.has_headers,
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
-        } else {
-            app.settings.dialog_msg = DialogMessage::StartOfFile;
-            app.settings.dialog_open = true
+    /// Makes the change chosen from a menu of the table to the edits of the open file.
+    fn apply_edit_action(app: &mut ViewerApp, action: EditAction) {
+        let edit = match action {
+            EditAction::InsertRow(row) => Edit::InsertRow { row },
+            EditAction::DeleteRow(row) => Edit::DeleteRow { row },
+            EditAction::InsertColumn(column) => Edit::InsertColumn {
+                column,
+                name: String::from("New Column"),
+            },
+            EditAction::DeleteColumn(column) => Edit::DeleteColumn { column },
+            EditAction::RenameColumn(column) => {
+                app.settings.rename_text = app.headers.get(column).unwrap_or("").to_string();
+                app.settings.rename_column = Some(column);
+                return;
+            }
+        };
+        record_edit(app, edit);
+    }
+
+    /// Makes `edit` to the open file and adds it to the history, so it can be undone.
+    fn record_edit(app: &mut ViewerApp, edit: Edit) {
+        let edit = match fit_edit_to_file(app, edit) {
+            Some(edit) => edit,
+            None => return,
+        };
+        if app.history.apply(edit, &mut app.edits) {
+            refresh_edits(app);
         }
     }
 
-    ///Shows last page with the number of rows specified.
-    fn show_last_page(app: &mut ViewerApp) {
-        if app.file_info.total_rows > app.settings.num_rows_to_display {
-            app.settings.current_pos = app.file_info.total_rows - app.settings.num_rows_to_display;
-            app.records = get_records_from_pos(
-                app.file_path.clone(),
-                app.settings.current_pos.clone(),
-                app.settings.num_rows_to_display.clone(),
-                app.file_info.has_headers,
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
-        } else {
-            app.settings.dialog_msg = DialogMessage::EndOfFile;
-            app.settings.dialog_open = true;
+    /// Returns `edit` as it can be saved to the open file, or `None` if it cannot be.
+    /// A file without a header row has nowhere to keep column names, so renames are
+    /// refused and inserted columns are left unnamed.
+    fn fit_edit_to_file(app: &ViewerApp, edit: Edit) -> Option<Edit> {
+        if app.file_info.has_headers {
+            return Some(edit);
+        }
+        match edit {
+            Edit::RenameColumn { .. } => {
+                println!("Error: Cannot Rename Column: The file has no header row");
+                None
+            }
+            Edit::InsertColumn { column, .. } => Some(Edit::InsertColumn {
+                column,
+                name: String::new(),
+            }),
+            edit => Some(edit),
         }
     }
 
-    /// Open a delimited data file and read in their headers and records.
-    /// Uses the OS file dialog window by utilising RUSTY FILE DIALOGS by .
-    fn open_file(app: &mut ViewerApp) {
-        if let Some(path) = FileDialog::new().pick_file() {
-            app.file_path = Option::from(path.display().to_string());
-            app.file_info.total_rows = get_row_count(app.file_path.clone());
-            // let mut reader:Reader<File> = ReaderBuilder::new().has_headers(app.file_info.has_headers).from_path(app.file_path.clone().unwrap()).unwrap();
-            app.headers = get_headers_from_file(
-                app.file_path.clone().unwrap(),
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
-            app.records = get_records_from_pos(
-                app.file_path.clone(),
-                app.settings.current_pos.clone(),
-                app.settings.num_rows_to_display,
-                app.file_info.has_headers,
-                get_delimiter(app.file_info.delimiter.clone()),
-            );
-            app.app_state = AppState::Viewer;
+    /// Undoes the last change made to the open file.
+    fn undo_edit(app: &mut ViewerApp) {
+        if app.history.undo(&mut app.edits) {
+            refresh_edits(app);
         }
     }
 
-    /// Displays the sorter window within the context of the Viewer App frame.
-    /// Displays the headers of the CSV file as buttons, that when clicked, select that column for sorting the file by
-    /// before exporting the file to a user specified location.
-    fn show_sorter_window(app: &mut ViewerApp, ctx: &Context, frame: &mut Frame) {
-        let mut current_index = 0;
-        let mut output_path = String::from("");
+    /// Makes again the last change undone.
+    fn redo_edit(app: &mut ViewerApp) {
+        if app.history.redo(&mut app.edits) {
+            refresh_edits(app);
+        }
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            egui::Window::new("Sort File")
-                .collapsible(false)
-                .resizable(true)
-                .anchor(Align2::CENTER_CENTER, (Vec2 { x: 0.0, y: 0.0 }))
-                .show(ctx, |ui| {
-                    ui.heading("File Info");
-                    ui.label(format!("Filepath: {}", app.file_path.clone().unwrap()));
-                    ui.separator();
+    /// Makes the changes stored in a recipe file, chosen with the file dialog, to the open file.
+    /// Changes referring to rows or columns the file does not have are skipped.
+    fn apply_recipe(app: &mut ViewerApp) {
+        let path = match FileDialog::new().add_filter("Recipe", &["csv"]).pick_file() {
+            Some(path) => path.display().to_string(),
+            None => return,
+        };
+        match load_recipe(&path) {
+            Ok(edits) => {
+                let mut skipped = 0;
+                for edit in edits {
+                    let applied = match fit_edit_to_file(app, edit) {
+                        Some(edit) => app.history.apply(edit, &mut app.edits),
+                        None => false,
+                    };
+                    if !applied {
+                        skipped += 1;
+                    }
+                }
+                if skipped > 0 {
+                    println!("Skipped {} changes of the recipe", skipped);
+                }
+                refresh_edits(app);
+            }
+            Err(e) => println!("Error: Cannot Apply Recipe: {}", e),
+        }
+    }
 
-                    ui.heading("Header Fields:");
-                    // ui.label(format!("{:?}", app.headers));
+    /// Shows the changes made to the open file since the edits changed, reading the rows again.
+    /// Inserting or deleting rows and columns returns a sorted or filtered view to the file order,
+    /// as the view was made from the file on disk.
+    fn refresh_edits(app: &mut ViewerApp) {
+        if app.headers.len() != app.edits.headers().len() {
+            app.settings.hidden_columns.clear();
+            app.settings.selection = None;
+        }
+        app.headers = app.edits.headers();
+        app.settings.editing_cell = None;
+        if (app.edits.rows_changed() || app.edits.columns_changed()) && !is_view_unchanged(app) {
+            app.view_order = None;
+            app.view_filter = None;
+            update_view_rows(app);
+            reset_view(app);
+        }
+        app.records.clear();
+    }
+
+    /// Shows the history panel, listing the changes made to the open file. Clicking a change
+    /// undoes or redoes the changes after it or up to it, and the changes undone are greyed out.
+    fn show_history_panel(app: &mut ViewerApp, ctx: &Context) {
+        egui::SidePanel::right("history_panel")
+            .resizable(true)
+            .default_width(200.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("History");
+                    if ui.button("✖").clicked() {
+                        app.settings.show_history = false;
+                    }
+                });
+                ui.separator();
+                let mut clicked: Option<usize> = None;
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let applied = app.history.applied();
+                    if ui.selectable_label(applied == 0, "Opened File").clicked() {
+                        clicked = Some(0);
+                    }
+                    for (i, edit) in app.history.edits().iter().enumerate() {
+                        let text = if i < applied {
+                            egui::RichText::new(edit.describe())
+                        } else {
+                            egui::RichText::new(edit.describe()).weak()
+                        };
+                        if ui.selectable_label(applied == i + 1, text).clicked() {
+                            clicked = Some(i + 1);
+                        }
+                    }
+                });
+                if let Some(applied) = clicked {
+                    app.history.go_to(applied, &mut app.edits);
+                    refresh_edits(app);
+                }
+            });
+    }
+
+    /// Opens the window to type the new name of the column being renamed.
+    fn show_rename_column_window(app: &mut ViewerApp, ctx: &Context) {
+        egui::Window::new("Rename Column")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2 { x: 0.0, y: 0.0 })
+            .show(ctx, |ui| {
+                let response = ui.text_edit_singleline(&mut app.settings.rename_text);
+                ui.horizontal(|ui| {
+                    if ui.button("Rename").clicked()
+                        || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                    {
+                        if let Some(column) = app.settings.rename_column.take() {
+                            let name = app.settings.rename_text.clone();
+                            record_edit(app, Edit::RenameColumn { column, name });
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        app.settings.rename_column = None;
+                    }
+                });
+            });
+    }
+
+    /// Returns the columns of the open file shown in the table, in order.
+    fn get_shown_columns(app: &ViewerApp) -> Vec<usize> {
+        (0..app.headers.len())
+            .filter(|column| !app.settings.hidden_columns.contains(column))
+            .collect()
+    }
+
+    /// Exports the rows of the view to `format`, in the order they are shown, leaving out the
+    /// hidden columns. CSV is written with the options chosen in the CSV export window.
+    /// Once the file has been edited, the rows are exported as shown, with the edits applied.
+    fn export_view(app: &mut ViewerApp, format: ExportFormat) {
+        let path = match FileDialog::new()
+            .add_filter(format.name(), &[format.extension()])
+            .save_file()
+        {
+            Some(path) => path.display().to_string(),
+            None => return,
+        };
+        let mut dialect = app.file_info.dialect.clone();
+        if app.settings.csv_export_delimiter != Delimiter::Auto {
+            dialect.delimiter = get_delimiter(app.settings.csv_export_delimiter.clone()) as u8;
+        }
+        dialect.terminator = app.settings.csv_export_terminator;
+        let options = ExportOptions {
+            format,
+            columns: get_shown_columns(app),
+            table_name: get_table_name(&path),
+            dialect,
+            quoting: app.settings.csv_export_quoting,
+            bom: app.settings.csv_export_bom,
+            write_headers: app.file_info.has_headers,
+        };
+        let view = match (get_view_range(app, 0, get_view_len(app)), &app.row_index) {
+            (Some(rows), Some(index)) => Some((index, rows)),
+            _ => None,
+        };
+        let file_path = app.file_path.clone().unwrap();
+        let exported = if app.edits.is_modified() {
+            export_blocks(
+                &file_path,
+                &path,
+                &options,
+                &app.headers,
+                get_view_len(app),
+                |start, n| get_rows(app, start, n),
+            )
+        } else {
+            export_file(
+                &file_path,
+                &path,
+                &options,
+                &app.headers,
+                view,
+                app.file_info.has_headers,
+                &app.file_info.dialect,
+            )
+        };
+        match exported {
+            Ok(_) => {
+                app.settings.dialog_msg = DialogMessage::ExportedView;
+                app.settings.dialog_open = true;
+            }
+            Err(e) => println!("Error: Cannot Export File: {}", e),
+        }
+    }
+
+    /// Copies the selected cells to the clipboard as text in `format`, leaving out hidden columns.
+    fn copy_selection(app: &mut ViewerApp, ctx: &Context, format: CopyFormat) {
+        let selection = match app.settings.selection {
+            Some(selection) => selection,
+            None => return,
+        };
+        let shown_columns = get_shown_columns(app);
+        let columns: Vec<usize> = selection
+            .columns()
+            .filter_map(|shown| shown_columns.get(shown).copied())
+            .collect();
+        let headers: StringRecord = columns
+            .iter()
+            .map(|column| app.headers.get(*column).unwrap_or(""))
+            .collect();
+        let rows = selection.rows();
+        let records: Vec<StringRecord> = get_rows(app, *rows.start(), rows.count())
+            .iter()
+            .map(|record| {
+                columns
+                    .iter()
+                    .map(|column| record.get(*column).unwrap_or(""))
+                    .collect()
+            })
+            .collect();
+        match copy_records(&headers, &records, format) {
+            Ok(text) => ctx.output_mut(|o| o.copied_text = text),
+            Err(e) => println!("Error: Cannot Copy Cells: {}", e),
+        }
+    }
+
+    /// Pastes tabular `text` over the cells from the top left selected cell, as one change.
+    /// Cells that would fall beyond the last row or column of the view are left out.
+    fn paste_text(app: &mut ViewerApp, text: &str) {
+        let selection = match app.settings.selection {
+            Some(selection) => selection,
+            None => return,
+        };
+        let pasted = match parse_pasted(text) {
+            Ok(pasted) => pasted,
+            Err(e) => {
+                println!("Error: Cannot Paste Cells: {}", e);
+                return;
+            }
+        };
+        let top = *selection.rows().start();
+        let left = *selection.columns().start();
+        let shown_columns = get_shown_columns(app);
+        let mut cells = Vec::new();
+        let mut bottom_right = (top, left);
+        for (pos, values) in (top..get_view_len(app)).zip(pasted) {
+            // a sorted or filtered view shows rows of the file out of order
+            let row = get_row_number(app, pos);
+            for (shown, value) in (left..shown_columns.len()).zip(values) {
+                cells.push((row, shown_columns[shown], value));
+                bottom_right = (pos, bottom_right.1.max(shown));
+            }
+        }
+        if cells.is_empty() {
+            return;
+        }
+        record_edit(app, Edit::SetCells { cells });
+        app.settings.selection = Some(Selection {
+            anchor: (top, left),
+            cursor: bottom_right,
+        });
+    }
+
+    /// Fills the background of a table cell, to mark the row gone to with Go To Line.
+    fn paint_row_highlight(ui: &mut Ui) {
+        let rect = ui.max_rect();
+        ui.painter().rect_filled(
+            rect,
+            0.0,
+            ui.visuals().selection.bg_fill.linear_multiply(0.4),
+        );
+    }
+
+    /// Fills the background of a table cell, to mark it as selected.
+    fn paint_selection(ui: &mut Ui) {
+        let rect = ui.max_rect();
+        ui.painter().rect_filled(
+            rect,
+            0.0,
+            ui.visuals().selection.bg_fill.linear_multiply(0.6),
+        );
+    }
+
+    /// Returns the matches within data row `row`, from `cells` sorted in file order.
+    fn get_row_matches(cells: &[CellMatch], row: usize) -> &[CellMatch] {
+        let start = cells.partition_point(|cell| cell.row < row);
+        let end = cells.partition_point(|cell| cell.row <= row);
+        &cells[start..end]
+    }
+
+    /// Lays out the text of `cell` with each of `matches` highlighted, and the `current` match
+    /// highlighted in a stronger colour. A match of an empty cell is shown as a highlighted space.
+    fn highlight_cell(
+        ui: &Ui,
+        cell: &str,
+        matches: &[&CellMatch],
+        current: Option<&CellMatch>,
+    ) -> LayoutJob {
+        let plain = TextFormat::simple(
+            egui::TextStyle::Body.resolve(ui.style()),
+            ui.visuals().text_color(),
+        );
+        let mut job = LayoutJob::default();
+        let mut end = 0;
+        for found in matches {
+            let background = if current == Some(*found) {
+                CURRENT_MATCH_COLOR
+            } else {
+                MATCH_COLOR
+            };
+            let highlighted = TextFormat {
+                color: Color32::BLACK,
+                background,
+                ..plain.clone()
+            };
+            // skip a match that does not fall within the text, e.g. found in text since changed
+            let (before, text) = match (
+                cell.get(end..found.range.start),
+                cell.get(found.range.clone()),
+            ) {
+                (Some(before), Some(text)) => (before, text),
+                _ => continue,
+            };
+            job.append(before, 0.0, plain.clone());
+            if text.is_empty() {
+                job.append(" ", 0.0, highlighted);
+            } else {
+                job.append(text, 0.0, highlighted);
+            }
+            end = found.range.end;
+        }
+        job.append(&cell[end..], 0.0, plain);
+        job
+    }
+
+    /// Scrolls the table down by `num_rows_to_display` rows.
+    fn show_next_page(app: &mut ViewerApp) {
+        if app.settings.current_pos + app.settings.num_rows_to_display < get_view_len(app) {
+            scroll_to_pos(
+                app,
+                app.settings.current_pos + app.settings.num_rows_to_display,
+                egui::Align::Min,
+            );
+        } else
+        /* No more content in file */
+        {
+            app.settings.dialog_msg = DialogMessage::NextPage;
+            app.settings.dialog_open = true;
+        }
+    }
+
+    /// Scrolls the table up by `num_rows_to_display` rows, or to the top if there are fewer rows
+    /// above the current row position.
+    fn show_prev_page(app: &mut ViewerApp) {
+        let pos = app
+            .settings
+            .current_pos
+            .saturating_sub(app.settings.num_rows_to_display);
+        scroll_to_pos(app, pos, egui::Align::Min);
+    }
+
+    fn show_first_page(app: &mut ViewerApp) {
+        if app.settings.current_pos != 0 {
+            scroll_to_pos(app, 0, egui::Align::Min);
+        } else {
+            app.settings.dialog_msg = DialogMessage::StartOfFile;
+            app.settings.dialog_open = true
+        }
+    }
+
+    /// Scrolls the table to the last row of the view.
+    fn show_last_page(app: &mut ViewerApp) {
+        if get_view_len(app) > app.settings.num_rows_to_display {
+            scroll_to_pos(app, get_view_len(app) - 1, egui::Align::Max);
+        } else {
+            app.settings.dialog_msg = DialogMessage::EndOfFile;
+            app.settings.dialog_open = true;
+        }
+    }
+
+    /// Scrolls the table on the next frame so the row at `pos` of the view is aligned by `align`.
+    fn scroll_to_pos(app: &mut ViewerApp, pos: usize, align: egui::Align) {
+        app.settings.current_pos = pos;
+        app.settings.scroll_to = Some((pos, align));
+    }
+
+    /// Drops the cached rows and scrolls back to the top, for when the rows in the view change.
+    fn reset_view(app: &mut ViewerApp) {
+        app.records.clear();
+        app.settings.selection = None;
+        scroll_to_pos(app, 0, egui::Align::Min);
+    }
+
+    /// Sets the dialect used to parse the open file, and whether it has a header row.
+    /// Settings left on `Auto` are sniffed from the head of the file. The quoting style is
+    /// always sniffed.
+    fn detect_dialect(app: &mut ViewerApp) {
+        match sniff_file(&app.file_path.clone().unwrap()) {
+            Ok(sniffed) => {
+                app.file_info.dialect = sniffed.dialect;
+                if app.file_info.delimiter != Delimiter::Auto {
+                    app.file_info.dialect.delimiter =
+                        get_delimiter(app.file_info.delimiter.clone()) as u8;
+                }
+                app.file_info.has_headers = match app.file_info.header_row {
+                    HeaderRow::Yes => true,
+                    HeaderRow::No => false,
+                    HeaderRow::Auto => sniffed.has_headers,
+                };
+            }
+            Err(e) => {
+                println!("Error: Cannot Sniff Dialect: {}", e);
+                app.file_info.dialect =
+                    Dialect::with_delimiter(get_delimiter(app.file_info.delimiter.clone()));
+                app.file_info.has_headers = app.file_info.header_row != HeaderRow::No;
+            }
+        }
+    }
+
+    /// Reads `n` records starting at row `pos` of the open file, or of the view when the view is
+    /// sorted or filtered.
+    /// Seeks using the row index when one has been loaded, otherwise parses the file from the top.
+    /// The edits made in the viewer are applied to the records read.
+    fn get_rows(app: &ViewerApp, pos: usize, n: usize) -> Vec<StringRecord> {
+        if app.edits.rows_changed() {
+            return get_edited_rows(app, pos, n);
+        }
+        let records = read_rows(app, pos, n);
+        if !app.edits.is_modified() {
+            return records;
+        }
+        records
+            .iter()
+            .enumerate()
+            .map(|(line, record)| {
+                let row = get_row_number(app, pos + line);
+                app.edits.apply(RowId::File(row), Some(record))
+            })
+            .collect()
+    }
+
+    /// Reads `n` rows starting at row `pos` of the edited file, once rows have been inserted or
+    /// deleted. Those left of the file on disk are still in order, so they are read as one run.
+    fn get_edited_rows(app: &ViewerApp, pos: usize, n: usize) -> Vec<StringRecord> {
+        let end = pos.saturating_add(n).min(app.edits.len());
+        let ids: Vec<RowId> = (pos..end).filter_map(|p| app.edits.row_id(p)).collect();
+        let file_rows: Vec<usize> = ids
+            .iter()
+            .filter_map(|id| match id {
+                RowId::File(row) => Some(*row),
+                RowId::New(_) => None,
+            })
+            .collect();
+        let first = file_rows.first().copied().unwrap_or(0);
+        let records = match file_rows.last() {
+            Some(last) => read_rows(app, first, last - first + 1),
+            None => Vec::new(),
+        };
+        ids.iter()
+            .map(|id| match id {
+                RowId::File(row) => app.edits.apply(*id, records.get(row - first)),
+                RowId::New(_) => app.edits.apply(*id, None),
+            })
+            .collect()
+    }
+
+    /// Reads `n` rows of the file on disk starting at row `pos` of the view.
+    fn read_rows(app: &ViewerApp, pos: usize, n: usize) -> Vec<StringRecord> {
+        let view_range = get_view_range(app, pos, n);
+        if let (Some(rows), Some(index)) = (view_range, &app.row_index) {
+            // a row that cannot be read is shown empty, keeping the rows after it in place
+            return get_records_from_rows(
+                app.file_path.clone(),
+                index,
+                rows,
+                app.file_info.has_headers,
+                &app.file_info.dialect,
+            )
+            .into_iter()
+            .map(|record| record.unwrap_or_default())
+            .collect();
+        }
+        match &app.row_index {
+            Some(index) => get_records_from_index(
+                app.file_path.clone(),
+                index,
+                pos,
+                n,
+                app.file_info.has_headers,
+                &app.file_info.dialect,
+            ),
+            None => get_records_from_pos(
+                app.file_path.clone(),
+                pos,
+                n,
+                app.file_info.has_headers,
+                &app.file_info.dialect,
+            ),
+        }
+    }
+
+    /// Loads the row index of the open file from its sidecar, building it if it is missing or stale.
+    fn load_row_index(app: &mut ViewerApp) {
+        app.row_index = match RowIndex::load_or_build(
+            &app.file_path.clone().unwrap(),
+            &app.file_info.dialect,
+            DEFAULT_INDEX_STEP,
+        ) {
+            Ok(index) => Some(index),
+            Err(e) => {
+                println!("Error: Cannot Index File: {}", e);
+                None
+            }
+        };
+    }
+
+    /// Returns the number of data rows in the open file, not counting the header.
+    /// The record count is taken from the row index when one is loaded.
+    fn get_total_rows(app: &ViewerApp) -> usize {
+        let records = match &app.row_index {
+            Some(index) => index.total_records(),
+            None => get_row_count(app.file_path.clone(), &app.file_info.dialect),
+        };
+        if app.file_info.has_headers {
+            records.saturating_sub(1)
+        } else {
+            records
+        }
+    }
+
+    /// Sorts the rows shown in the viewer by `keys`, without changing the open file.
+    /// Rows are read from the sorted view by seeking, so the view can only be sorted once the file
+    /// has a row index.
+    fn sort_view(app: &mut ViewerApp, keys: Vec<SortKey>) {
+        // the view is sorted by the file on disk, which does not hold the edits
+        if app.edits.is_modified() {
+            println!("Error: Cannot Sort View: Save the Edits First");
+            return;
+        }
+        if app.row_index.is_none() {
+            println!("Error: Cannot Sort View: File Has No Row Index");
+            return;
+        }
+        match RowPermutation::build(
+            &app.file_path.clone().unwrap(),
+            keys,
+            &app.file_info.dialect,
+            app.file_info.has_headers,
+        ) {
+            Ok(order) => {
+                app.view_order = Some(order);
+                update_view_rows(app);
+                reset_view(app);
+            }
+            Err(e) => println!("Error: Cannot Sort View: {}", e),
+        }
+    }
+
+    /// Returns the view to the order of the rows in the file.
+    fn clear_view_sort(app: &mut ViewerApp) {
+        app.view_order = None;
+        update_view_rows(app);
+        reset_view(app);
+    }
+
+    /// Shows only the rows of the open file matching the filter query typed into the filter bar.
+    /// An empty query shows every row. Like sorting, filtering needs the row index.
+    fn apply_filter(app: &mut ViewerApp) {
+        if app.settings.filter_text.trim().is_empty() {
+            clear_filter(app);
+            return;
+        }
+        // the filter is matched against the file on disk, which does not hold the edits
+        if app.edits.is_modified() {
+            app.settings.filter_error = Some(String::from("Error: Save the Edits First"));
+            return;
+        }
+        if app.row_index.is_none() {
+            app.settings.filter_error = Some(String::from("Error: File Has No Row Index"));
+            return;
+        }
+        match RowFilter::build(
+            &app.file_path.clone().unwrap(),
+            &app.settings.filter_text,
+            &app.headers,
+            app.file_info.has_headers,
+            &app.file_info.dialect,
+        ) {
+            Ok(filter) => {
+                app.view_filter = Some(filter);
+                app.settings.filter_error = None;
+                update_view_rows(app);
+                reset_view(app);
+            }
+            Err(e) => app.settings.filter_error = Some(format!("Error: {}", e)),
+        }
+    }
+
+    /// Shows every row of the open file again.
+    fn clear_filter(app: &mut ViewerApp) {
+        app.view_filter = None;
+        app.settings.filter_error = None;
+        update_view_rows(app);
+        reset_view(app);
+    }
+
+    /// Shows the filter bar above the table, where a filter query such as
+    /// `State == "AL" and Population > 10000` can be typed to view only the matching rows.
+    fn show_filter_panel(app: &mut ViewerApp, ctx: &Context) {
+        egui::TopBottomPanel::top("filter_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter:");
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut app.settings.filter_text)
+                        .hint_text(r#"State == "AL" and Population > 10000"#)
+                        .desired_width(400.0),
+                );
+                if ui.button("Apply").clicked()
+                    || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                {
+                    apply_filter(app);
+                }
+                if ui
+                    .add_enabled(app.view_filter.is_some(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    app.settings.filter_text.clear();
+                    clear_filter(app);
+                }
+                if let Some(error) = &app.settings.filter_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            });
+        });
+    }
+
+    /// Describes the keys the view is sorted by, e.g. "State ⏶, Population ⏷".
+    fn get_sort_description(app: &ViewerApp, order: &RowPermutation) -> String {
+        let mut names: Vec<String> = Vec::new();
+        for key in order.keys.iter() {
+            let name = app.headers.get(key.column).unwrap_or("?");
+            let direction = if key.ascending { "⏶" } else { "⏷" };
+            names.push(format!("{} {}", name, direction));
+        }
+        names.join(", ")
+    }
+
+    /// Open a delimited data file and read in their headers and records.
+    /// Uses the OS file dialog window by utilising RUSTY FILE DIALOGS by .
+    fn open_file(app: &mut ViewerApp) {
+        if let Some(path) = FileDialog::new().pick_file() {
+            app.file_path = Option::from(path.display().to_string());
+            load_file(app);
+        }
+    }
+
+    /// Reads in the headers of the file at the app's `file_path`, dropping any view and edits of
+    /// the file open before.
+    fn load_file(app: &mut ViewerApp) {
+        app.view_order = None;
+        app.view_filter = None;
+        app.view_rows = None;
+        app.settings.hidden_columns.clear();
+        // matches of a previous search are rows of the file read before
+        app.settings.find_search = None;
+        app.settings.find_matching_cells.clear();
+        app.settings.find_matching_rows.clear();
+        app.settings.find_current_match = None;
+        app.settings.find_snippets.clear();
+        detect_dialect(app);
+        load_row_index(app);
+        app.file_info.total_rows = get_total_rows(app);
+        // let mut reader:Reader<File> = ReaderBuilder::new().has_headers(app.file_info.has_headers).from_path(app.file_path.clone().unwrap()).unwrap();
+        app.headers = match get_headers_from_file(
+            app.file_path.clone().unwrap(),
+            &app.file_info.dialect,
+            app.file_info.has_headers,
+        ) {
+            Ok(headers) => headers,
+            Err(e) => {
+                println!("Error: Cannot Read Headers: {}", e);
+                StringRecord::new()
+            }
+        };
+        app.edits = EditOverlay::new(&app.headers, app.file_info.total_rows);
+        app.history = EditHistory::new(&app.edits);
+        app.settings.editing_cell = None;
+        reset_view(app);
+        app.app_state = AppState::Viewer;
+    }
+
+    /// Writes the open file with the edits made in the viewer to `output_path`, in the dialect
+    /// of the open file, then opens the written file. Saving to the open file replaces it.
+    fn save_file(app: &mut ViewerApp, output_path: String) {
+        match app.edits.save(
+            &app.file_path.clone().unwrap(),
+            &output_path,
+            app.file_info.has_headers,
+            &app.file_info.dialect,
+        ) {
+            Ok(_) => {
+                app.file_path = Some(output_path);
+                load_file(app);
+                app.settings.dialog_msg = DialogMessage::SavedFile;
+                app.settings.dialog_open = true;
+            }
+            Err(e) => println!("Error: Cannot Save File: {}", e),
+        }
+    }
+
+    /// Displays the sorter window within the context of the Viewer App frame.
+    /// Displays the headers of the CSV file as buttons, that when clicked, add that column to the list of
+    /// sort keys. Each key can be set ascending or descending, given a comparison type, moved or removed
+    /// before sorting the view, or sorting the file and exporting it to a user specified location.
+    /// Exporting leaves the open file and the view as they are.
+    fn show_sorter_window(app: &mut ViewerApp, ctx: &Context, frame: &mut Frame) {
+        let mut current_index = 0;
+        let mut output_path = String::from("");
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::Window::new("Sort File")
+                .collapsible(false)
+                .resizable(true)
+                .anchor(Align2::CENTER_CENTER, (Vec2 { x: 0.0, y: 0.0 }))
+                .show(ctx, |ui| {
+                    ui.heading("File Info");
+                    ui.label(format!("Filepath: {}", app.file_path.clone().unwrap()));
+                    ui.separator();
+
+                    ui.heading("Header Fields:");
+                    // ui.label(format!("{:?}", app.headers));
                     ui.horizontal_wrapped(|ui| {
                         for header in app.headers.into_iter() {
                             if ui.button(header).clicked() {
-                                app.settings.index_selected_header = current_index.clone()
+                                app.settings.sort_keys.push(SortKey::new(current_index));
                             };
                             current_index = current_index + 1;
                         }
                     });
-                    ui.label(format!(
-                        "Selected Header: {:?}",
-                        app.headers.get(app.settings.index_selected_header).unwrap()
-                    ));
+                    ui.separator();
+
+                    ui.heading("Sort By:");
+                    if app.settings.sort_keys.is_empty() {
+                        ui.label("Click a header field to add it as a sort key.");
+                    }
+                    show_sort_key_list(app, ui);
+                    ui.separator();
+
+                    ui.label("Output Delimiter:");
+                    ui.horizontal(|ui| {
+                        let output_delimiter = &mut app.settings.sort_output_delimiter;
+                        ui.radio_value(output_delimiter, Delimiter::Auto, "SAME");
+                        ui.radio_value(output_delimiter, Delimiter::Comma, "COMMA");
+                        ui.radio_value(output_delimiter, Delimiter::Tab, "TAB");
+                        ui.radio_value(output_delimiter, Delimiter::Semicolon, "SEMICOLON");
+                        ui.radio_value(output_delimiter, Delimiter::Pipe, "PIPE");
+                        ui.radio_value(output_delimiter, Delimiter::Colon, "COLON");
+                    });
                     ui.separator();
 
                     ui.horizontal_wrapped(|ui| {
-                        if ui.button("Sort and Export as...").clicked() {
+                        if ui
+                            .add_enabled(
+                                !app.settings.sort_keys.is_empty()
+                                    && app.row_index.is_some()
+                                    && !app.edits.is_modified(),
+                                egui::Button::new("Sort View"),
+                            )
+                            .clicked()
+                        {
+                            sort_view(app, app.settings.sort_keys.clone());
+                            app.app_state = AppState::Viewer;
+                        }
+                        if ui
+                            .add_enabled(
+                                !app.settings.sort_keys.is_empty(),
+                                egui::Button::new("Sort and Export as..."),
+                            )
+                            .clicked()
+                        {
                             ui.add(egui::widgets::Spinner::new());
 
                             // Choose Export path
                             if let Some(path) = FileDialog::new().save_file() {
                                 output_path = path.display().to_string();
+                                let mut output_dialect = app.file_info.dialect.clone();
+                                if app.settings.sort_output_delimiter != Delimiter::Auto {
+                                    output_dialect.delimiter =
+                                        get_delimiter(app.settings.sort_output_delimiter.clone())
+                                            as u8;
+                                }
                                 match sort_records(
                                     app.file_path.clone().unwrap(),
                                     output_path.clone(),
-                                    app.settings.index_selected_header,
+                                    &app.settings.sort_keys,
+                                    &app.file_info.dialect,
+                                    app.file_info.has_headers,
+                                    &output_dialect,
                                 ) {
                                     Ok(_) => {
-                                        app.settings.current_pos = 0;
-                                        app.file_path = Option::from(output_path.clone());
-                                        app.file_info.total_rows =
-                                            get_row_count(app.file_path.clone());
-                                        app.records = get_records_from_pos(
-                                            app.file_path.clone(),
-                                            0,
-                                            app.settings.num_rows_to_display,
-                                            true,
-                                            get_delimiter(app.file_info.delimiter.clone()),
-                                        );
-
                                         app.settings.dialog_msg = DialogMessage::ExportedFile;
                                         app.settings.dialog_open = true;
                                     }
-                                    Err(_) => {
-                                        println!("Error: Cannot Sort Records");
+                                    Err(e) => {
+                                        println!("Error: Cannot Sort Records: {}", e);
                                     }
                                 }
                             }
@@ -620,6 +2050,69 @@ pub mod viewer_app {
         });
     }
 
+    /// Lists the sort keys in priority order, with controls for the direction and comparison type of
+    /// each key, and buttons to move a key up the list or remove it.
+    fn show_sort_key_list(app: &mut ViewerApp, ui: &mut Ui) {
+        let mut move_up: Option<usize> = None;
+        let mut remove: Option<usize> = None;
+
+        for (i, key) in app.settings.sort_keys.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{}. {}",
+                    i + 1,
+                    app.headers.get(key.column).unwrap_or("")
+                ));
+                ui.selectable_value(&mut key.ascending, true, "Ascending");
+                ui.selectable_value(&mut key.ascending, false, "Descending");
+                egui::ComboBox::from_id_source(("sort_key_compare", i))
+                    .selected_text(format!("{:?}", key.compare))
+                    .show_ui(ui, |ui| {
+                        for compare in CompareType::ALL {
+                            ui.selectable_value(
+                                &mut key.compare,
+                                compare,
+                                format!("{:?}", compare),
+                            );
+                        }
+                    });
+                if key.compare == CompareType::Numeric {
+                    ui.label("Decimal:");
+                    ui.selectable_value(&mut key.options.decimal_separator, '.', "1.5");
+                    ui.selectable_value(&mut key.options.decimal_separator, ',', "1,5");
+                }
+                if key.compare == CompareType::Date {
+                    let mut format = key.options.date_format.clone().unwrap_or_default();
+                    if ui
+                        .add(
+                            egui::TextEdit::singleline(&mut format)
+                                .hint_text("ISO-8601, or e.g. %d/%m/%Y")
+                                .desired_width(150.0),
+                        )
+                        .changed()
+                    {
+                        key.options.date_format = Some(format).filter(|f| !f.is_empty());
+                    }
+                }
+                ui.selectable_value(&mut key.options.nulls, NullOrder::First, "Empty First");
+                ui.selectable_value(&mut key.options.nulls, NullOrder::Last, "Empty Last");
+                if ui.add_enabled(i > 0, egui::Button::new("Up")).clicked() {
+                    move_up = Some(i);
+                }
+                if ui.button("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+
+        if let Some(i) = move_up {
+            app.settings.sort_keys.swap(i - 1, i);
+        }
+        if let Some(i) = remove {
+            app.settings.sort_keys.remove(i);
+        }
+    }
+
     /// Opens the find window inside the frame of the Viewer App.
     /// The find window displays as a small popup window above the viewer interface.
     /// Interacting with the "Find Matches" button calls `find_matching_rows()` function from find.rs.
@@ -631,64 +2124,115 @@ pub mod viewer_app {
             .default_pos(Pos2 { x: 0.0, y: 0.0 })
             .show(ctx, |ui| {
                 let response = ui.add(
-                    egui::TextEdit::singleline(&mut app.settings.find_string).desired_width(250.0),
+                    egui::TextEdit::singleline(&mut app.settings.find_query.text)
+                        .desired_width(250.0),
                 );
+                let mut options_changed = false;
+                ui.horizontal(|ui| {
+                    let query = &mut app.settings.find_query;
+                    options_changed |= ui
+                        .checkbox(&mut query.case_insensitive, "Ignore Case")
+                        .changed();
+                    options_changed |= ui.checkbox(&mut query.whole_word, "Whole Word").changed();
+                    options_changed |= ui.checkbox(&mut query.whole_cell, "Whole Cell").changed();
+                    options_changed |= ui.checkbox(&mut query.regex, "Regex").changed();
+                });
+                let scope = match app.settings.find_query.columns.len() {
+                    0 => String::from("Columns: All"),
+                    n => format!("Columns: {}", n),
+                };
+                ui.menu_button(scope, |ui| {
+                    let query = &mut app.settings.find_query;
+                    if ui.button("Search All Columns").clicked() {
+                        query.columns.clear();
+                        options_changed = true;
+                    }
+                    for (column, header) in app.headers.iter().enumerate() {
+                        let mut searched = query.columns.contains(&column);
+                        if ui.checkbox(&mut searched, header).changed() {
+                            if searched {
+                                query.columns.push(column);
+                                query.columns.sort();
+                            } else {
+                                query.columns.retain(|c| *c != column);
+                            }
+                            options_changed = true;
+                        }
+                    }
+                });
                 ui.label(format!(
-                    "(Case-Sensitive) Searching for: {:?}",
-                    app.settings.find_string.clone()
+                    "Searching for: {:?}",
+                    app.settings.find_query.text.clone()
                 ));
                 if ui.button("Find Matches").clicked() {
-                    app.settings.find_matches_index = 0;
-                    app.settings.find_matching_rows = find_matching_rows(
-                        app.file_path.clone(),
-                        app.settings.find_string.clone(),
-                        app.file_info.has_headers.clone(),
-                    );
-                    println!("{:?}", app.settings.find_matching_rows.clone());
+                    find_matches(app);
                 }
-                if response.changed() {
-                    println!("Response Changed");
+                if response.changed() || options_changed {
+                    app.settings.find_matching_cells = Vec::new();
                     app.settings.find_matching_rows = Vec::new();
-                    app.settings.find_matches_index = 0;
+                    app.settings.find_current_match = None;
+                    app.settings.find_snippets.clear();
+                    app.settings.find_search = None;
+                    app.settings.replace_preview = Vec::new();
+                    app.settings.find_error = None;
                 }
                 if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                    app.settings.find_matches_index = 0;
-                    app.settings.find_matching_rows = find_matching_rows(
-                        app.file_path.clone(),
-                        app.settings.find_string.clone(),
-                        app.file_info.has_headers.clone(),
-                    );
-                    println!("{:?}", app.settings.find_matching_rows.clone());
+                    find_matches(app);
+                }
+                if let Some(error) = &app.settings.find_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
                 }
                 ui.label(format!(
-                    "Number of matches: {:?}",
+                    "Number of matches: {:?} in {:?} rows",
+                    app.settings.find_matching_cells.len(),
                     app.settings.find_matching_rows.len()
                 ));
+                if let Some(search) = &app.settings.find_search {
+                    let (scanned, total) = search.bytes();
+                    let mut cancelled = false;
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::ProgressBar::new(search.progress())
+                                .desired_width(200.0)
+                                .text(format!(
+                                    "{:.1} / {:.1} MB",
+                                    scanned as f64 / (1024.0 * 1024.0),
+                                    total as f64 / (1024.0 * 1024.0)
+                                )),
+                        );
+                        cancelled = ui.button("Cancel").clicked();
+                    });
+                    if cancelled {
+                        search.cancel();
+                    }
+                }
                 // ui.label(format!("Number of matches: {:?}", app.settings.find_matching_rows.clone()));
                 ui.horizontal(|ui| {
+                    ui.checkbox(&mut app.settings.find_wrap, "Wrap Around");
+                    ui.checkbox(&mut app.settings.show_replace, "Replace");
+                });
+                if app.settings.show_replace {
+                    show_replace_controls(app, ui);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Show Previous").clicked() {
+                        let previous = previous_match(
+                            app.settings.find_matching_cells.len(),
+                            app.settings.find_current_match,
+                            app.settings.find_wrap,
+                        );
+                        show_match_or_notify(app, previous);
+                    }
                     if ui.button("Show Next").clicked() {
-                        if app.settings.find_matching_rows.len() > 0 {
-                            let row_matching: usize = find_row_of_next(
-                                app.settings.find_matching_rows.clone(),
-                                app.settings.find_matches_index.clone(),
-                            );
-                            println!("current row matching: {:?}", row_matching);
-                            app.records = get_records_from_pos(
-                                app.file_path.clone(),
-                                row_matching - 1,
-                                app.settings.num_rows_to_display,
-                                app.file_info.has_headers.clone(),
-                                get_delimiter(app.file_info.delimiter.clone()),
-                            );
-                            app.settings.current_pos = row_matching - 1;
-                            if app.settings.find_matches_index
-                                < app.settings.find_matching_rows.len() - 1
-                            {
-                                app.settings.find_matches_index += 1;
-                            } else {
-                                app.settings.find_matches_index = 0;
-                            }
-                        }
+                        let next = next_match(
+                            app.settings.find_matching_cells.len(),
+                            app.settings.find_current_match,
+                            app.settings.find_wrap,
+                        );
+                        show_match_or_notify(app, next);
+                    }
+                    if ui.button("Results").clicked() {
+                        app.settings.show_find_results = !app.settings.show_find_results;
                     }
                     if ui.button("Close").clicked() {
                         app.settings.show_finder = false;
@@ -697,6 +2241,343 @@ pub mod viewer_app {
             });
     }
 
+    /// Makes the `i`th matching cell the current match, and scrolls it into view.
+    fn show_match(app: &mut ViewerApp, i: usize) {
+        let row = app.settings.find_matching_cells[i].row;
+        // matches are rows of the file, find where they are in a sorted or filtered view
+        match get_view_position(app, row) {
+            Some(pos) => scroll_to_pos(app, pos, egui::Align::Center),
+            None => println!("Row {} is hidden by the filter", row + 1),
+        }
+        app.settings.find_current_match = Some(i);
+    }
+
+    /// Shows the replacement text box of the find window, with a preview of the cells a replacement
+    /// would change and a button to write the replaced file out as a new file.
+    /// In regex mode the replacement can use the capture groups of the query, e.g. `$1`.
+    fn show_replace_controls(app: &mut ViewerApp, ui: &mut Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Replace With:");
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut app.settings.replace_with).desired_width(180.0),
+                )
+                .changed()
+            {
+                app.settings.replace_preview = Vec::new();
+            }
+        });
+        // replacing reads the file on disk, which does not hold the edits
+        let unedited = !app.edits.is_modified();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(unedited, egui::Button::new("Preview"))
+                .on_disabled_hover_text("Save the edits first")
+                .clicked()
+            {
+                match preview_replacements(
+                    app.file_path.clone().unwrap(),
+                    &app.settings.find_query,
+                    &app.settings.replace_with,
+                    app.file_info.has_headers,
+                    &app.file_info.dialect,
+                    REPLACE_PREVIEW_LIMIT,
+                ) {
+                    Ok(changes) => {
+                        app.settings.replace_preview = changes;
+                        app.settings.find_error = None;
+                    }
+                    Err(e) => app.settings.find_error = Some(format!("Error: {}", e)),
+                }
+            }
+            if ui
+                .add_enabled(unedited, egui::Button::new("Replace All and Export as..."))
+                .on_disabled_hover_text("Save the edits first")
+                .clicked()
+            {
+                if let Some(path) = FileDialog::new().save_file() {
+                    match replace_to_file(
+                        app.file_path.clone().unwrap(),
+                        path.display().to_string(),
+                        &app.settings.find_query,
+                        &app.settings.replace_with,
+                        app.file_info.has_headers,
+                        &app.file_info.dialect,
+                    ) {
+                        Ok(changed) => {
+                            println!("Replaced {} cells", changed);
+                            app.settings.dialog_msg = DialogMessage::ReplacedFile;
+                            app.settings.dialog_open = true;
+                        }
+                        Err(e) => app.settings.find_error = Some(format!("Error: {}", e)),
+                    }
+                }
+            }
+        });
+
+        if !app.settings.replace_preview.is_empty() {
+            ui.label(format!(
+                "Preview of the first {} changed cells:",
+                app.settings.replace_preview.len()
+            ));
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("replace_preview")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Row");
+                            ui.strong("Column");
+                            ui.strong("Before");
+                            ui.strong("After");
+                            ui.end_row();
+                            for change in app.settings.replace_preview.iter() {
+                                ui.label(format!("{}", change.row + 1));
+                                ui.label(app.headers.get(change.column).unwrap_or("?"));
+                                ui.label(&change.before);
+                                ui.label(&change.after);
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+        ui.separator();
+    }
+
+    /// Shows the match at `i`, or tells the user there are no more matches when `i` is `None`.
+    fn show_match_or_notify(app: &mut ViewerApp, i: Option<usize>) {
+        match i {
+            Some(i) => show_match(app, i),
+            None if !app.settings.find_matching_cells.is_empty() => {
+                app.settings.dialog_msg = DialogMessage::NoMoreMatches;
+                app.settings.dialog_open = true;
+            }
+            None => {}
+        }
+    }
+
+    /// Shows a side panel listing every match of the last search with a snippet of its cell.
+    /// Clicking an entry jumps to that match.
+    fn show_find_results_panel(app: &mut ViewerApp, ctx: &Context) {
+        egui::SidePanel::right("find_results_panel")
+            .resizable(true)
+            .default_width(250.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Find Results");
+                    if ui.button("✖").clicked() {
+                        app.settings.show_find_results = false;
+                    }
+                });
+                ui.label(format!(
+                    "{} matches for {:?}",
+                    app.settings.find_matching_cells.len(),
+                    app.settings.find_query.text
+                ));
+                ui.separator();
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                let count = app.settings.find_matching_cells.len();
+                let mut clicked: Option<usize> = None;
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show_rows(ui, row_height, count, |ui, range| {
+                        load_find_snippets(app, range.clone());
+                        for i in range {
+                            let found = &app.settings.find_matching_cells[i];
+                            let snippet = app
+                                .settings
+                                .find_snippets
+                                .get(&i)
+                                .map(|snippet| snippet.as_str())
+                                .unwrap_or("");
+                            let text = format!(
+                                "{}:{} {}",
+                                found.row + 1,
+                                app.headers.get(found.column).unwrap_or("?"),
+                                snippet
+                            );
+                            let selected = app.settings.find_current_match == Some(i);
+                            if ui.selectable_label(selected, text).clicked() {
+                                clicked = Some(i);
+                            }
+                        }
+                    });
+                if let Some(i) = clicked {
+                    show_match(app, i);
+                }
+            });
+    }
+
+    /// Reads the cells of the matches in `range` that have no snippet yet, and stores their
+    /// snippets. The rows are read with the row index, so no snippets are loaded without one.
+    fn load_find_snippets(app: &mut ViewerApp, range: Range<usize>) {
+        let index = match &app.row_index {
+            Some(index) => index,
+            None => return,
+        };
+        let missing: Vec<usize> = range
+            .filter(|i| !app.settings.find_snippets.contains_key(i))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        let mut rows: Vec<usize> = missing
+            .iter()
+            .map(|i| app.settings.find_matching_cells[*i].row)
+            .collect();
+        rows.dedup();
+        let records = get_records_from_rows(
+            app.file_path.clone(),
+            index,
+            &rows,
+            app.file_info.has_headers,
+            &app.file_info.dialect,
+        );
+        for i in missing {
+            let found = &app.settings.find_matching_cells[i];
+            let cell = rows
+                .iter()
+                .position(|row| *row == found.row)
+                .and_then(|line| records.get(line))
+                .and_then(|record| record.as_ref())
+                .and_then(|record| record.get(found.column));
+            let snippet = match cell {
+                Some(cell) => get_snippet(cell, &found.range, 20),
+                None => String::new(),
+            };
+            app.settings.find_snippets.insert(i, snippet);
+        }
+    }
+
+    /// Starts searching the open file for cells matching the find query in the background,
+    /// replacing any previous matches. Matches are added by `poll_find_search` as they arrive.
+    fn find_matches(app: &mut ViewerApp) {
+        app.settings.find_current_match = None;
+        app.settings.find_snippets.clear();
+        app.settings.find_matching_cells = Vec::new();
+        app.settings.find_matching_rows = Vec::new();
+        app.settings.find_search = None;
+        match BackgroundSearch::start(
+            app.file_path.clone().unwrap(),
+            &app.settings.find_query,
+            app.file_info.has_headers,
+            &app.file_info.dialect,
+        ) {
+            Ok(search) => {
+                app.settings.find_search = Some(search);
+                app.settings.find_error = None;
+            }
+            Err(e) => {
+                app.settings.find_error = Some(format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Collects the matches found by the background search since the last frame. Keeps the UI
+    /// repainting until the search finishes, so that the count and progress stay live.
+    fn poll_find_search(app: &mut ViewerApp, ctx: &Context) {
+        let search = match &mut app.settings.find_search {
+            Some(search) => search,
+            None => return,
+        };
+        let cells = search.poll();
+        let finished = search.is_finished();
+        for cell in cells.iter() {
+            if app.settings.find_matching_rows.last() != Some(&cell.row) {
+                app.settings.find_matching_rows.push(cell.row);
+            }
+        }
+        app.settings.find_matching_cells.extend(cells);
+
+        if finished {
+            app.settings.find_search = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Opens the Go To Line window, which takes a row number, or a physical line number of the file,
+    /// and shows the page starting at that row with the row highlighted.
+    fn show_goto_window(app: &mut ViewerApp, ctx: &Context) {
+        egui::Window::new("Go To Line")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2 { x: 0.0, y: 0.0 })
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut app.settings.goto_physical_line, false, "Row");
+                    ui.radio_value(&mut app.settings.goto_physical_line, true, "Line of File");
+                });
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut app.settings.goto_text).desired_width(150.0),
+                );
+                ui.label(format!("Rows: 1 to {}", app.file_info.total_rows));
+                if let Some(error) = &app.settings.goto_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Go").clicked()
+                        || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                    {
+                        match get_goto_row(app) {
+                            Ok(row) => {
+                                go_to_row(app, row);
+                                app.settings.goto_error = None;
+                                app.settings.show_goto = false;
+                            }
+                            Err(e) => app.settings.goto_error = Some(e),
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        app.settings.goto_error = None;
+                        app.settings.show_goto = false;
+                    }
+                });
+            });
+    }
+
+    /// Parses the number typed into Go To Line, returning the file row it refers to. Row numbers
+    /// start at 1 as shown in the table, and must be within the file.
+    fn get_goto_row(app: &ViewerApp) -> Result<usize, String> {
+        let number: u64 = match app.settings.goto_text.trim().parse() {
+            Ok(number) if number > 0 => number,
+            _ => return Err(String::from("Enter a number from 1")),
+        };
+        let row = if app.settings.goto_physical_line {
+            let record =
+                match get_record_at_line(app.file_path.clone(), &app.file_info.dialect, number) {
+                    Some(record) => record,
+                    None => return Err(format!("The file has fewer than {} lines", number)),
+                };
+            if app.file_info.has_headers {
+                match record.checked_sub(1) {
+                    Some(row) => row,
+                    None => return Err(format!("Line {} is the header row", number)),
+                }
+            } else {
+                record
+            }
+        } else {
+            (number - 1) as usize
+        };
+        if row >= app.file_info.total_rows {
+            return Err(format!("There are only {} rows", app.file_info.total_rows));
+        }
+        if get_view_position(app, row).is_none() {
+            return Err(format!("Row {} is hidden by the filter", row + 1));
+        }
+        Ok(row)
+    }
+
+    /// Scrolls file row `row` to the top of the table, highlighting the row.
+    fn go_to_row(app: &mut ViewerApp, row: usize) {
+        let pos = get_view_position(app, row).unwrap_or(0);
+        scroll_to_pos(app, pos, egui::Align::Min);
+        app.settings.goto_row = Some(row);
+    }
+
     /// Opens a dialog box within the eframe that displays passed string slice.
     /// The dialog box window remains open on top of the displayed content until the "okay" button is
     /// clicked by the user.
@@ -724,6 +2605,9 @@ pub mod viewer_app {
             .resizable(false)
             .anchor(Align2::CENTER_CENTER, Vec2 { x: 0.0, y: 0.0 })
             .show(ctx, |ui| {
+                if app.edits.is_modified() {
+                    ui.label("Unsaved changes to the file will be lost.");
+                }
                 ui.horizontal(|ui| {
                     if ui.button("Cancel").clicked() {
                         app.settings.quit_confirmation = false;
@@ -736,23 +2620,98 @@ pub mod viewer_app {
             });
     }
 
-    /// Launches the GUI for the Viewer App
-    pub fn run_app() -> eframe::Result<()> {
+    /// Opens a dialog window that checks if the user really wants to lose the unsaved changes to the
+    /// open file. "Discard" goes on to `action`, "Cancel" keeps the file and its changes open.
+    fn show_discard_confirmation(app: &mut ViewerApp, ctx: &Context, action: DiscardAction) {
+        egui::Window::new("Discard unsaved changes?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2 { x: 0.0, y: 0.0 })
+            .show(ctx, |ui| {
+                ui.label("Unsaved changes to the file will be lost.");
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        app.settings.discard_confirmation = None;
+                    }
+                    if ui.button("Discard").clicked() {
+                        app.settings.discard_confirmation = None;
+                        run_discard_action(app, action);
+                    }
+                });
+            });
+    }
+
+    /// Goes on to `action`, first asking the user to confirm if the open file has unsaved changes.
+    fn discard_edits_then(app: &mut ViewerApp, action: DiscardAction) {
+        if app.edits.is_modified() {
+            app.settings.discard_confirmation = Some(action);
+        } else {
+            run_discard_action(app, action);
+        }
+    }
+
+    fn run_discard_action(app: &mut ViewerApp, action: DiscardAction) {
+        match action {
+            DiscardAction::Open => open_file(app),
+            DiscardAction::Close => close_file(app),
+        }
+    }
+
+    /// Closes the opened file and returns to main menu.
+    fn close_file(app: &mut ViewerApp) {
+        app.headers = StringRecord::new();
+        app.records.clear();
+        app.file_path = None;
+        app.row_index = None;
+        app.view_order = None;
+        app.view_filter = None;
+        app.view_rows = None;
+        app.edits = EditOverlay::default();
+        app.history = EditHistory::default();
+        app.file_info = FileInfo::default();
+        app.settings = AppSettings::default();
+        app.app_state = AppState::MainMenu;
+    }
+
+    /// Starts the viewer with the options given on the command line. The file given is opened
+    /// straight away; without one, data piped in on stdin is opened, as is the file `-`.
+    pub fn run_app(args: Args) -> eframe::Result<()> {
         let mut viewer_app = ViewerApp::default();
+        if let Some(delimiter) = args.delimiter {
+            viewer_app.file_info.delimiter = get_delimiter_setting(delimiter);
+        }
+        viewer_app.file_info.header_row = match args.has_headers {
+            Some(true) => HeaderRow::Yes,
+            Some(false) => HeaderRow::No,
+            None => HeaderRow::Auto,
+        };
 
-        // checks if run from terminal without a file passed in
-        // if atty::isnt(atty::Stream::Stdin) {
-        //     // Open from passed file
-        //     let mut reader: Reader<Stdin> = get_reader_stdin();
-        //     viewer_app = ViewerApp {
-        //         app_state: AppState::Viewer,
-        //         file_info: Default::default(),
-        //         headers: get_headers_stdin(reader.borrow_mut()),
-        //         records: get_records_stdin(reader.borrow_mut()),
-        //         file_path: None,
-        //         settings: Default::default(),
-        //     };
-        // }
+        let piped = args.file_path.is_none() && atty::isnt(atty::Stream::Stdin);
+        if piped || args.file_path.as_deref() == Some("-") {
+            // stdin can only be read once, so it is copied to a file that can be seeked in
+            match spool_to_temp_file(io::stdin().lock()) {
+                // started from a launcher, stdin is often empty rather than a terminal
+                Ok(path) if fs::metadata(&path).map_or(true, |m| m.len() == 0) => {
+                    let _ = fs::remove_file(path);
+                }
+                Ok(path) => viewer_app.spooled_file = Some(path),
+                Err(e) => println!("Error: Cannot Read Stdin: {}", e),
+            }
+        }
+        viewer_app.file_path = match &viewer_app.spooled_file {
+            Some(path) => Some(path.clone()),
+            None => args.file_path.filter(|path| path != "-"),
+        };
+        if viewer_app.file_path.is_some() {
+            load_file(&mut viewer_app);
+            if let Some(row) = args.goto {
+                viewer_app.settings.goto_text = row.to_string();
+                match get_goto_row(&viewer_app) {
+                    Ok(row) => go_to_row(&mut viewer_app, row),
+                    Err(e) => println!("Error: Cannot Go To Row: {}", e),
+                }
+            }
+        }
 
         let mut eframe_options = eframe::NativeOptions::default();
         eframe_options.maximized = true;